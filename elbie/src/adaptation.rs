@@ -0,0 +1,171 @@
+use crate::errors::ElbieError;
+use crate::language::Language;
+use crate::phoneme::PHONEME;
+use crate::phoneme::Phoneme;
+use crate::word::Word;
+use core::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/*
+NOTE: Adapting a loanword happens in two steps.
+
+First, each phoneme of the word is mapped to the closest phoneme in the target language. A phoneme with the same name is the closest, otherwise it's the one which shares the most sets with it, and has the fewest sets that aren't shared. This only works as well as the sets are named consistently between the languages, which they should be if the IPA constants are used.
+
+Second, if the mapped word isn't valid in the target language, it is repaired by the cheapest series of edits which makes it valid:
+- substituting one of the closest phonemes for a phoneme costs 1.
+- inserting a phoneme (epenthesis) costs 2. Only phonemes in the target language's epenthetic set can be inserted, if it has one (see `Language::set_epenthetic_set`).
+- deleting a phoneme costs 3.
+
+If more than one result has the same cost, all of them are returned. The search gives up after `MAX_REPAIR_COST`, since the number of candidates grows very quickly.
+*/
+
+pub(crate) const MAX_REPAIR_COST: usize = 4;
+const SUBSTITUTION_COST: usize = 1;
+const EPENTHESIS_COST: usize = 2;
+const DELETION_COST: usize = 3;
+// how many of the closest phonemes can be substituted for a phoneme
+const SUBSTITUTE_COUNT: usize = 3;
+
+pub(crate) struct Adaptation {
+    pub mapped: Word,
+    pub adapted: Vec<Word>,
+    // None if no valid result was found
+    pub cost: Option<usize>
+}
+
+// The order phonemes are ranked by for closeness, smallest first.
+fn closeness(name: &str, sets: &[&'static str], candidate: &Rc<Phoneme>, candidate_sets: &[&'static str]) -> (bool, Reverse<usize>, usize, &'static str) {
+    let shared = sets.iter().filter(|set| candidate_sets.contains(set)).count();
+    let different = sets.len() + candidate_sets.len() - (shared * 2);
+    (candidate.name != name, Reverse(shared), different, candidate.name)
+}
+
+struct Adapter<'language> {
+    from: &'language Language,
+    to: &'language Language,
+    phonemes: Vec<(Rc<Phoneme>, Vec<&'static str>)>,
+    epenthetic: Vec<Rc<Phoneme>>,
+    // the closest other phonemes in the target language for each of its phonemes
+    substitutes: HashMap<&'static str, Vec<Rc<Phoneme>>>
+}
+
+impl<'language> Adapter<'language> {
+    fn new(from: &'language Language, to: &'language Language) -> Result<Self, ElbieError> {
+        let phonemes: Vec<_> = to.inventory().get_set(PHONEME)?.iter().map(|phoneme| (phoneme.clone(), to.inventory().sets_containing(phoneme))).collect();
+
+        let mut substitutes = HashMap::new();
+        for (phoneme, sets) in &phonemes {
+            let mut ranked: Vec<_> = phonemes.iter().filter(|(candidate, _)| candidate != phoneme).collect();
+            ranked.sort_by_key(|(candidate, candidate_sets)| closeness(phoneme.name, sets, candidate, candidate_sets));
+            _ = substitutes.insert(phoneme.name, ranked.into_iter().take(SUBSTITUTE_COUNT).map(|(candidate, _)| candidate.clone()).collect());
+        }
+
+        Ok(Self { from,
+                  to,
+                  phonemes,
+                  epenthetic: to.inventory().get_set(to.epenthetic_set())?.to_vec(),
+                  substitutes })
+    }
+
+    fn closest(&self, phoneme: &Rc<Phoneme>) -> Option<Rc<Phoneme>> {
+        let sets = self.from.inventory().sets_containing(phoneme);
+        self.phonemes.iter().min_by_key(|(candidate, candidate_sets)| closeness(phoneme.name, &sets, candidate, candidate_sets)).map(|(candidate, _)| candidate.clone())
+    }
+
+    fn is_valid(&self, word: &Word) -> Result<bool, ElbieError> {
+        Ok(self.to.check_word(word, None)?.is_ok())
+    }
+
+    fn edits(&self, word: &Word) -> Vec<(usize, Word)> {
+        let phonemes = word.phonemes();
+        let mut result = Vec::new();
+
+        for (i, phoneme) in phonemes.iter().enumerate() {
+            for substitute in self.substitutes.get(phoneme.name).into_iter().flatten() {
+                let mut edited = phonemes.clone();
+                if let Some(position) = edited.get_mut(i) {
+                    *position = substitute.clone();
+                }
+                result.push((SUBSTITUTION_COST, Word::from(edited)));
+            }
+        }
+
+        for i in 0..=phonemes.len() {
+            for inserted in &self.epenthetic {
+                let mut edited = phonemes.clone();
+                edited.insert(i, inserted.clone());
+                result.push((EPENTHESIS_COST, Word::from(edited)));
+            }
+        }
+
+        // a word has to have something left in it.
+        if phonemes.len() > 1 {
+            for i in 0..phonemes.len() {
+                let mut edited = phonemes.clone();
+                _ = edited.remove(i);
+                result.push((DELETION_COST, Word::from(edited)));
+            }
+        }
+
+        result
+    }
+
+    fn repair(&self, mapped: &Word) -> Result<Option<(usize, Vec<Word>)>, ElbieError> {
+        let mut best_costs = HashMap::from([(mapped.clone(), 0)]);
+        let mut frontier = BTreeMap::from([(0, vec![mapped.clone()])]);
+
+        while let Some((cost, words)) = frontier.pop_first() {
+            if cost > MAX_REPAIR_COST {
+                break;
+            }
+
+            // a word might have been found more cheaply after it was added at this cost.
+            let words: Vec<_> = words.into_iter().filter(|word| best_costs.get(word) == Some(&cost)).collect();
+
+            let mut valid = Vec::new();
+            for word in &words {
+                if self.is_valid(word)? && !valid.contains(word) {
+                    valid.push(word.clone());
+                }
+            }
+            if !valid.is_empty() {
+                valid.sort_by_key(ToString::to_string);
+                return Ok(Some((cost, valid)));
+            }
+
+            for word in words {
+                for (edit_cost, edited) in self.edits(&word) {
+                    let edited_cost = cost + edit_cost;
+                    if edited_cost <= MAX_REPAIR_COST && best_costs.get(&edited).is_none_or(|best| edited_cost < *best) {
+                        _ = best_costs.insert(edited.clone(), edited_cost);
+                        frontier.entry(edited_cost).or_default().push(edited);
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Adapts a word from one language into another. See the note at the top of this file.
+pub(crate) fn adapt_word(from: &Language, to: &Language, word: &Word) -> Result<Adaptation, ElbieError> {
+    let adapter = Adapter::new(from, to)?;
+
+    let mut mapped = Vec::new();
+    for phoneme in word.phonemes() {
+        mapped.push(adapter.closest(phoneme).ok_or(ElbieError::SetIsEmpty(PHONEME))?);
+    }
+    let mapped = Word::from(mapped);
+
+    Ok(match adapter.repair(&mapped)? {
+        Some((cost, adapted)) => Adaptation { mapped,
+                                              adapted,
+                                              cost: Some(cost) },
+        None => Adaptation { mapped,
+                             adapted: Vec::new(),
+                             cost: None }
+    })
+}
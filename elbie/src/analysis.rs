@@ -421,7 +421,7 @@ impl AnalysisConfig<'_> {
         self.find_set_for_phoneme(phoneme, &self.structure_sets)
     }
 
-    fn find_cluster_set_for_phoneme(&self, phoneme: &Rc<Phoneme>) -> Result<&'static str, ElbieError> {
+    pub(crate) fn find_cluster_set_for_phoneme(&self, phoneme: &Rc<Phoneme>) -> Result<&'static str, ElbieError> {
         self.find_set_for_phoneme(phoneme, &self.cluster_sets)
     }
 
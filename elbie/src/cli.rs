@@ -1,24 +1,40 @@
 use crate::cli_functions::OrthographyIndex;
 use crate::cli_functions::TransformationOption;
 use crate::cli_functions::ValidateOption;
+use crate::cli_functions::adapt_words;
 use crate::cli_functions::analyze_words;
+use crate::cli_functions::check_orthography;
+use crate::cli_functions::check_transformations;
+use crate::cli_functions::cognate_table;
+use crate::cli_functions::derive_words;
+use crate::cli_functions::find_ancestors;
 use crate::cli_functions::format_lexicon;
 use crate::cli_functions::generate_words;
 use crate::cli_functions::show_phonemes;
+use crate::cli_functions::show_pronunciation_guide;
 use crate::cli_functions::show_spelling;
+use crate::cli_functions::sound_correspondences;
+use crate::cli_functions::spell_texts;
+use crate::cli_functions::transform_timeline;
 use crate::cli_functions::transform_words;
+use crate::cli_functions::transformation_stats;
 use crate::cli_functions::validate_words;
+use crate::correspondence::read_cognate_pairs;
 use crate::errors::ElbieError;
 use crate::family::Family;
+use crate::family_tree::FamilyTree;
+use crate::family_tree::FamilyTreeFormat;
 use crate::format::Format;
 use crate::language::Language;
 use crate::lexicon::LexiconStyle;
+use crate::transformation::TransformationEntry;
 use crate::word_table::WordTable;
 use core::convert::identity;
 use core::error::Error;
 use gumdrop::Options;
 use std::env;
 use std::ffi::OsStr;
+use std::fs;
 use std::io;
 use std::io::Write;
 use std::io::stdout;
@@ -64,7 +80,11 @@ pub struct GenerateWords {
 
     #[options(no_short)]
     /// Turns off column and row spanning in headers of grid output.
-    no_spans: bool
+    no_spans: bool,
+
+    #[options(no_short)]
+    /// Adds the narrow phonetic transcription of each word, if the language has allophonic rules.
+    phonetic: bool
 }
 
 impl DoIt for GenerateWords {
@@ -81,7 +101,7 @@ impl DoIt for GenerateWords {
 
         let language = family.get_language_or_default(language.as_deref())?;
 
-        generate_words(Some(grid_style), language, self.count, output)?;
+        generate_words(Some(grid_style), language, self.count, self.phonetic, output)?;
 
         Ok(true)
     }
@@ -98,6 +118,10 @@ pub struct ValidateWords {
     /// On success, traces the validation through only successful branches and patterns
     explain: bool,
 
+    #[options(no_short)]
+    /// Adds the narrow phonetic transcription of each valid word, if the language has allophonic rules.
+    phonetic: bool,
+
     #[options(default = "plain")]
     #[options(no_short)]
     /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
@@ -140,6 +164,7 @@ impl DoIt for ValidateWords {
                            (false, true) => ValidateOption::Trace,
                            (false, false) => ValidateOption::Simple
                        },
+                       self.phonetic,
                        &self.format,
                        output)?;
 
@@ -193,6 +218,10 @@ pub struct ShowSpelling {
     /// If more than 1, breaks table and splits it across the specified number of column groups for easier formatting.
     columns: usize,
 
+    #[options(no_short)]
+    /// Also prints out tables of glyph information for orthographies which are written in a native script.
+    glyphs: bool,
+
     #[options(default = "terminal")]
     #[options(no_short)]
     /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
@@ -217,23 +246,55 @@ impl DoIt for ShowSpelling {
 
         let language = family.get_language_or_default(language.as_deref())?;
 
-        show_spelling(Some(grid_style), language, self.columns, output)?;
+        show_spelling(Some(grid_style), language, self.columns, self.glyphs, output)?;
 
         Ok(true)
     }
 }
 
 #[derive(Options)]
-/// Loads a lexicon of words in CSV format for a language, expecting legal phonemes, and prints out a formatted listing. Requires a file name to load and an orthography index to spell the main entries. Options allow controlling the output format and style. The format specifies the markup format for the output. The style specifies whether a list or a table is required. The input CSV must have a "word" column containing the phonetic transcription of the word, and a "definition" column containing arbitrary text.
+/// Prints out a pronunciation guide for a language, for readers who don't know IPA. Each phoneme is given an English-friendly respelling, a description, and approximate English equivalents. Options include controlling the output format.
+pub struct PronunciationGuide {
+    #[options(default = "terminal")]
+    #[options(no_short)]
+    /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
+    format: Format,
+
+    #[options(no_short)]
+    /// Turns off column and row spanning in headers of grid output.
+    no_spans: bool
+}
+
+impl DoIt for PronunciationGuide {
+    fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, language: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        let grid_style = if self.no_spans {
+            &self.format.with_no_spans()
+        } else {
+            &self.format
+        };
+
+        let mut family = family()?;
+
+        family.load_language_or_default(language.as_deref())?;
+
+        let language = family.get_language_or_default(language.as_deref())?;
+
+        show_pronunciation_guide(grid_style, language, output)?;
+
+        Ok(true)
+    }
+}
+
+#[derive(Options)]
+/// Loads a lexicon of words in CSV format for a language, expecting legal phonemes, and prints out a formatted listing. Requires a file name to load and one or more orthographies to spell the main entries. Options allow controlling the output format and style. The format specifies the markup format for the output. The style specifies whether a list or a table is required. The input CSV must have a "word" column containing the phonetic transcription of the word, and a "definition" column containing arbitrary text.
 pub struct FormatLexicon {
     #[options(required)]
     /// Read the list of words from CSV files, can be specified multiple times to merge multiple files.
     file: Vec<String>,
 
     #[options(required)]
-    /// Orthography index (0-based) to use for generating main entries.
-    // TODO: Should be able to support multiple spellings as with transformation.
-    spelling: usize,
+    /// Orthography name or index (0-based) to use for generating main entries. Can be specified multiple times to show more than one spelling for the main entries, specify "all" to use all orthographies.
+    spelling: Vec<OrthographyIndex>,
 
     /// Changes the format of output. Values include "plain", "terminal", "markdown", "html", "json", and "csv". "plain" and "terminal" have the same output when `--style` is "list".
     #[options(default = "plain")]
@@ -245,6 +306,10 @@ pub struct FormatLexicon {
     #[options(no_short)]
     style: LexiconStyle,
 
+    #[options(no_short)]
+    /// Adds the narrow phonetic transcription of each word, if the language has allophonic rules.
+    phonetic: bool,
+
     #[options(no_short)]
     /// Turns off column and row spanning in headers of grid output.
     no_spans: bool
@@ -277,7 +342,108 @@ impl DoIt for FormatLexicon {
 
         let word_data = word_data.ok_or("Please specify at least one file to load.")?;
 
-        format_lexicon(grid_style, &self.style, language, &word_data, self.spelling, output)?;
+        format_lexicon(grid_style, &self.style, language, &word_data, &self.spelling, self.phonetic, output)?;
+
+        Ok(true)
+    }
+}
+
+#[derive(Options)]
+/// Looks for distinct words which would be spelled the same way in an orthography, such as digraphs which could be confused with two separate phonemes. Words can be passed at the end of the command or read from CSV files, with a header indicating field names. If there is more than one field, the one named "word" will be used. Options also allow checking every valid word up to a certain length.
+pub struct OrthographyCheck {
+    #[options(no_short)]
+    /// Also check every word up to this many phonemes which the phonotactics allow. The number of words grows very quickly, so keep this small.
+    length: Option<usize>,
+
+    /// Orthography name or index (0-based) to check, can be specified multiple times. If not specified, all orthographies are checked.
+    #[options(no_short)]
+    spelling: Vec<OrthographyIndex>,
+
+    #[options(default = "plain")]
+    #[options(no_short)]
+    /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
+    format: Format,
+
+    #[options(no_short)]
+    /// Turns off column and row spanning in headers of grid output.
+    no_spans: bool,
+
+    /// Read the list of words from CSV files, can be specified multiple times to merge multiple files.
+    file: Vec<String>,
+
+    #[options(free)]
+    /// Words to check
+    words: Vec<String>
+}
+
+impl DoIt for OrthographyCheck {
+    fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, language: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        let grid_style = if self.no_spans {
+            &self.format.with_no_spans()
+        } else {
+            &self.format
+        };
+
+        let mut family = family()?;
+
+        family.load_language_or_default(language.as_deref())?;
+
+        let language = family.get_language_or_default(language.as_deref())?;
+
+        let mut word_data = WordTable::default();
+
+        word_data.add_words(&self.words);
+
+        for file in &self.file {
+            let data = WordTable::read(file)?;
+            word_data.combine_with(data);
+        }
+
+        if word_data.is_empty() && self.length.is_none() {
+            return Err("Please specify words to check, or a length to check all words up to.".into());
+        }
+
+        check_orthography(language, &word_data, self.length, &self.spelling, grid_style, output)
+    }
+}
+
+#[derive(Options)]
+/// Spells a phonemic text in the orthographies of a language. The text can be read from files or passed at the end of the command. Words are separated by whitespace, punctuation at the start and end of words is kept, a '+' inside a word marks a morpheme boundary, and a '*' at the start of a word marks a proper noun. Capitalization and separators depend on the text style of each orthography.
+pub struct SpellText {
+    /// Read the text from files, can be specified multiple times to join multiple files.
+    file: Vec<String>,
+
+    /// Orthography name or index (0-based) to spell the text in, can be specified multiple times. If not specified, the text is spelled in all orthographies.
+    #[options(no_short)]
+    spelling: Vec<OrthographyIndex>,
+
+    #[options(free)]
+    /// Text to spell
+    text: Vec<String>
+}
+
+impl DoIt for SpellText {
+    fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, language: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        let mut family = family()?;
+
+        family.load_language_or_default(language.as_deref())?;
+
+        let language = family.get_language_or_default(language.as_deref())?;
+
+        let mut text = self.text.join(" ");
+
+        for file in &self.file {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(&fs::read_to_string(file)?);
+        }
+
+        if text.trim().is_empty() {
+            return Err("Please specify text to spell.".into());
+        }
+
+        spell_texts(language, &text, &self.spelling, output)?;
 
         Ok(true)
     }
@@ -290,6 +456,10 @@ pub struct Transform {
     /// The target transformation. Used to lookup the transformation even if dont_validate is true.
     target: String,
 
+    #[options(no_short)]
+    /// Treats the target as a language, and transforms through the shortest chain of transformations leading to it, following the language each one validates as. Every step of the chain is shown.
+    chain: bool,
+
     #[options(no_short)]
     /// Requests that the words not be validated after transformation.
     dont_validate: bool,
@@ -306,15 +476,23 @@ pub struct Transform {
     /// Provides detailed explanation of valid phonemes on success.
     explain: bool,
 
+    #[options(no_short)]
+    /// Outputs a derivation table instead, with a column for each rule showing the word after that rule changed it, and every variant in free variation separated by a tilde. The replace-word and spelling options are ignored.
+    derivation: bool,
+
     #[options(default = "plain")]
     #[options(no_short)]
     /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
     format: Format,
 
+    #[options(no_short)]
+    /// Turns off column and row spanning in headers of grid output.
+    no_spans: bool,
+
     /// Read the list of words from CSV files, can be specified multiple times to merge multiple files.
     file: Vec<String>,
 
-    /// Also emit trasncriptions in the following orthography index (multiple orthographies can be included, specify "all" to show all orthographies)
+    /// Also emit trasncriptions in the following orthography name or index (multiple orthographies can be included, specify "all" to show all orthographies)
     #[options(no_short)]
     spelling: Vec<OrthographyIndex>,
 
@@ -325,16 +503,30 @@ pub struct Transform {
 
 impl DoIt for Transform {
     fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, language: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        let grid_style = if self.no_spans {
+            &self.format.with_no_spans()
+        } else {
+            &self.format
+        };
+
         let mut family = family()?;
 
         let source_language = language.or_else(|| family.default_language_name().map(ToOwned::to_owned)).ok_or(ElbieError::NoDefaultLanguage)?;
 
         // in theory the transformation should take care of loading any languages it needs to get phonemes from. If it doesn't, they'll get an error pretty quickly.
-        family.load_transformation(&source_language, &self.target)?;
+        let steps = if self.chain {
+            Some(family.find_transformation_chain(&source_language, &self.target)?)
+        } else {
+            family.load_transformation(&source_language, &self.target)?;
+            None
+        };
 
         let source_language = family.get_language(&source_language)?;
 
-        let transformations = family.get_transformations(source_language.name(), &self.target, !self.dont_validate)?;
+        let transformations = match &steps {
+            Some(steps) => family.get_chain_transformations(steps, !self.dont_validate)?,
+            None => family.get_transformations(source_language.name(), &self.target, !self.dont_validate)?
+        };
 
         let mut word_data = WordTable::default();
 
@@ -349,6 +541,10 @@ impl DoIt for Transform {
             return Err("Please specify words to transform.".into());
         }
 
+        if self.derivation {
+            return derive_words(source_language, &transformations, &word_data, grid_style, output);
+        }
+
         if transform_words(source_language,
                            &transformations,
                            word_data,
@@ -360,7 +556,7 @@ impl DoIt for Transform {
                                (false, true) => TransformationOption::Trace,
                                (false, false) => TransformationOption::Simple
                            },
-                           &self.format,
+                           grid_style,
                            output)?
         {
             Ok(true)
@@ -371,6 +567,390 @@ impl DoIt for Transform {
     }
 }
 
+#[derive(Options)]
+/// Runs a transformation over a list of words and reports how each rule affected them.
+pub struct TransformStats {
+    #[options(required)]
+    /// The target transformation.
+    target: String,
+
+    #[options(no_short)]
+    /// Treats the target as a language, and reports on the shortest chain of transformations leading to it, following the language each one validates as.
+    chain: bool,
+
+    #[options(no_short)]
+    /// Requests that the words not be validated after transformation.
+    dont_validate: bool,
+
+    #[options(default = "plain")]
+    #[options(no_short)]
+    /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
+    format: Format,
+
+    /// Read the list of words from CSV files, can be specified multiple times to merge multiple files.
+    file: Vec<String>,
+
+    #[options(free)]
+    /// Words to transform
+    words: Vec<String>
+}
+
+impl DoIt for TransformStats {
+    fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, language: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        let mut family = family()?;
+
+        let source_language = language.or_else(|| family.default_language_name().map(ToOwned::to_owned)).ok_or(ElbieError::NoDefaultLanguage)?;
+
+        let steps = if self.chain {
+            Some(family.find_transformation_chain(&source_language, &self.target)?)
+        } else {
+            family.load_transformation(&source_language, &self.target)?;
+            None
+        };
+
+        let source_language = family.get_language(&source_language)?;
+
+        let transformations = match &steps {
+            Some(steps) => family.get_chain_transformations(steps, !self.dont_validate)?,
+            None => family.get_transformations(source_language.name(), &self.target, !self.dont_validate)?
+        };
+
+        let mut word_data = WordTable::default();
+
+        word_data.add_words(&self.words);
+
+        for file in &self.file {
+            let data = WordTable::read(file)?;
+            word_data.combine_with(data);
+        }
+
+        if word_data.is_empty() {
+            return Err("Please specify words to transform.".into());
+        }
+
+        transformation_stats(source_language, &transformations, &word_data, &self.format, output)
+    }
+}
+
+#[derive(Options)]
+/// Searches for words in the source language which transform into the specified words.
+pub struct Ancestors {
+    #[options(required)]
+    /// The transformation to reverse. Sets are not allowed.
+    target: String,
+
+    #[options(no_short, required)]
+    /// Search every valid source word up to this many phonemes. This should be at least the length of the longest word, plus the number of phonemes the rules might have deleted. Every one of those words is transformed, and the number grows very quickly with the length, so a search beyond 5 or 6 phonemes can take a long time.
+    length: usize,
+
+    #[options(default = "plain")]
+    #[options(no_short)]
+    /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
+    format: Format,
+
+    /// Read the list of words from CSV files, can be specified multiple times to merge multiple files.
+    file: Vec<String>,
+
+    #[options(free)]
+    /// Words in the target language
+    words: Vec<String>
+}
+
+impl DoIt for Ancestors {
+    fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, language: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        let mut family = family()?;
+
+        let source_language = language.or_else(|| family.default_language_name().map(ToOwned::to_owned)).ok_or(ElbieError::NoDefaultLanguage)?;
+
+        family.load_transformation(&source_language, &self.target)?;
+
+        let source_language = family.get_language(&source_language)?;
+
+        let transformations = family.get_transformations(source_language.name(), &self.target, false)?;
+        let [transformation] = transformations.as_slice() else {
+            return Err(ElbieError::TransformationSetsNotAllowedHere.into());
+        };
+
+        let mut word_data = WordTable::default();
+
+        word_data.add_words(&self.words);
+
+        for file in &self.file {
+            let data = WordTable::read(file)?;
+            word_data.combine_with(data);
+        }
+
+        if word_data.is_empty() {
+            return Err("Please specify words to search for.".into());
+        }
+
+        find_ancestors(source_language, transformation, &word_data, self.length, &self.format, output)
+    }
+}
+
+#[derive(Options)]
+/// Shows what each word in a lexicon becomes in every language descended from the source language. Every transformation from the source language is followed, and then every transformation from the languages those validate as, and so on. If two transformations lead to the same language, only the first one found is followed further.
+pub struct Cognates {
+    #[options(no_short)]
+    /// Orthography name or index (0-based) to spell the words in. Languages without that orthography, and transformations which don't validate, are shown in phonemic form. It is an error if none of the languages have it.
+    spelling: Option<OrthographyIndex>,
+
+    #[options(default = "plain")]
+    #[options(no_short)]
+    /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
+    format: Format,
+
+    /// Read the list of words from CSV files, can be specified multiple times to merge multiple files.
+    file: Vec<String>,
+
+    #[options(free)]
+    /// Words in the source language
+    words: Vec<String>
+}
+
+impl DoIt for Cognates {
+    fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, language: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        if matches!(self.spelling, Some(OrthographyIndex::All)) {
+            return Err("Cognates can only be spelled in one orthography.".into());
+        }
+
+        let mut family = family()?;
+
+        let source_language = language.or_else(|| family.default_language_name().map(ToOwned::to_owned)).ok_or(ElbieError::NoDefaultLanguage)?;
+
+        family.load_language(&source_language)?;
+
+        let keys = family.load_descendants(&source_language)?;
+
+        let mut descendants = Vec::new();
+        for (from, name, parent) in keys {
+            for item in family.get_transformations(&from, &name, true)? {
+                descendants.push((item, parent));
+            }
+        }
+
+        let mut word_data = WordTable::default();
+
+        word_data.add_words(&self.words);
+
+        for file in &self.file {
+            let data = WordTable::read(file)?;
+            word_data.combine_with(data);
+        }
+
+        if word_data.is_empty() {
+            return Err("Please specify words to transform.".into());
+        }
+
+        cognate_table(family.get_language(&source_language)?, &descendants, &word_data, self.spelling.as_ref(), &self.format, output)
+    }
+}
+
+#[derive(Options)]
+/// Adapts loanwords from the source language into another language. Each phoneme is replaced with the closest phoneme in the target language, then the word is repaired with the fewest substitutions, insertions and deletions needed to make it valid.
+pub struct AdaptWords {
+    #[options(required)]
+    /// The language to adapt the words into.
+    target: String,
+
+    #[options(default = "plain")]
+    #[options(no_short)]
+    /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
+    format: Format,
+
+    /// Read the list of words from CSV files, can be specified multiple times to merge multiple files.
+    file: Vec<String>,
+
+    #[options(free)]
+    /// Words in the source language
+    words: Vec<String>
+}
+
+impl DoIt for AdaptWords {
+    fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, language: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        let mut family = family()?;
+
+        let source_language = language.or_else(|| family.default_language_name().map(ToOwned::to_owned)).ok_or(ElbieError::NoDefaultLanguage)?;
+
+        family.load_language(&source_language)?;
+        family.load_language(&self.target)?;
+
+        let mut word_data = WordTable::default();
+
+        word_data.add_words(&self.words);
+
+        for file in &self.file {
+            let data = WordTable::read(file)?;
+            word_data.combine_with(data);
+        }
+
+        if word_data.is_empty() {
+            return Err("Please specify words to adapt.".into());
+        }
+
+        adapt_words(family.get_language(&source_language)?, family.get_language(&self.target)?, &word_data, &self.format, output)
+    }
+}
+
+#[derive(Options)]
+/// Finds the sound correspondences between the source language and another language from pairs of cognates. Each pair is aligned phoneme by phoneme, matching phonemes which share the most sets. A correspondence is regular if it is found in at least the minimum number of pairs. Pairs with irregular correspondences are listed after the regular correspondences.
+pub struct Correspondences {
+    #[options(required)]
+    /// The language the cognates are compared with.
+    target: String,
+
+    #[options(required)]
+    /// Read the pairs of cognates from CSV files, which have a column named for each language. Can be specified multiple times.
+    file: Vec<String>,
+
+    #[options(default = "2")]
+    #[options(no_short)]
+    /// The number of pairs a correspondence must be found in to be regular.
+    min_count: usize,
+
+    #[options(default = "plain")]
+    #[options(no_short)]
+    /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
+    format: Format
+}
+
+impl DoIt for Correspondences {
+    fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, language: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        let mut family = family()?;
+
+        let source_language = language.or_else(|| family.default_language_name().map(ToOwned::to_owned)).ok_or(ElbieError::NoDefaultLanguage)?;
+
+        family.load_language(&source_language)?;
+        family.load_language(&self.target)?;
+
+        let mut pairs = Vec::new();
+        for file in &self.file {
+            pairs.extend(read_cognate_pairs(file, &source_language, &self.target)?);
+        }
+
+        if pairs.is_empty() {
+            return Err("Please specify pairs of cognates to compare.".into());
+        }
+
+        sound_correspondences(family.get_language(&source_language)?, family.get_language(&self.target)?, &pairs, self.min_count, &self.format, output)
+    }
+}
+
+#[derive(Options)]
+/// Shows the languages in the family, with the transformations between them. Sets and chains are shown with the transformations they're made of.
+pub struct ShowFamilyTree {
+    #[options(default = "markdown")]
+    #[options(no_short)]
+    /// Changes the format of the output. Values include "dot", "mermaid", "markdown" and "html". The first two are graphs for other tools to draw, the last two are nested lists.
+    format: FamilyTreeFormat
+}
+
+impl DoIt for ShowFamilyTree {
+    fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, _: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        let mut family = family()?;
+
+        let tree = FamilyTree::load(&mut family)?;
+
+        tree.print(&self.format, output)?;
+
+        Ok(true)
+    }
+}
+
+#[derive(Options)]
+/// Shows the forms of words at dates in a timeline of sound changes.
+pub struct ShowTimeline {
+    #[options(required)]
+    /// The name of the timeline.
+    timeline: String,
+
+    #[options(no_short)]
+    /// A date to show the words at. Can be specified multiple times. Use '--date=-500' for negative dates. Defaults to the date of every stage.
+    date: Vec<i64>,
+
+    #[options(no_short)]
+    #[options(default = "date")]
+    /// The attribute in the CSV files holding the date a word entered the language. Words without a date are applied to every stage.
+    date_attribute: String,
+
+    #[options(default = "plain")]
+    #[options(no_short)]
+    /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
+    format: Format,
+
+    /// Read the list of words from CSV files, can be specified multiple times to merge multiple files.
+    file: Vec<String>,
+
+    #[options(free)]
+    /// Words in the language of the first stage
+    words: Vec<String>
+}
+
+impl DoIt for ShowTimeline {
+    fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, _: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        let mut family = family()?;
+
+        family.load_timeline(&self.timeline)?;
+
+        let stages = family.get_timeline(&self.timeline, true)?;
+
+        let mut word_data = WordTable::default();
+
+        word_data.add_words(&self.words);
+
+        for file in &self.file {
+            let data = WordTable::read(file)?;
+            word_data.combine_with(data);
+        }
+
+        if word_data.is_empty() {
+            return Err("Please specify words to transform.".into());
+        }
+
+        transform_timeline(&stages, &word_data, &self.date, &self.date_attribute, &self.format, output)
+    }
+}
+
+#[derive(Options)]
+/// Checks the examples added to transformations and their rules.
+pub struct CheckTransformations {
+    /// The transformation to check. Can be specified multiple times. If not specified, every transformation from every language is checked.
+    target: Vec<String>,
+
+    #[options(default = "plain")]
+    #[options(no_short)]
+    /// Changes the format of grid output. Values include "plain", "terminal", "markdown", "html", "json", and "csv".
+    format: Format
+}
+
+impl DoIt for CheckTransformations {
+    fn doit<FamilyCreator: FnOnce() -> Result<Family, ElbieError>>(&self, family: FamilyCreator, language: Option<String>, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+        let mut family = family()?;
+
+        let mut keys = if self.target.is_empty() {
+            family.transformation_keys()
+        } else {
+            let source_language = language.or_else(|| family.default_language_name().map(ToOwned::to_owned)).ok_or(ElbieError::NoDefaultLanguage)?;
+            self.target.iter().map(|target| (source_language.clone(), target.clone())).collect()
+        };
+        keys.sort();
+
+        for (from, name) in &keys {
+            family.load_transformation(from, name)?;
+        }
+
+        let mut transformations = Vec::new();
+        for (from, name) in &keys {
+            // sets are checked through their items
+            if let TransformationEntry::Single(transformation) = family.get_transformation(from, name)? {
+                transformations.push((format!("{from} > {name}"), transformation));
+            }
+        }
+
+        check_transformations(&transformations, &self.format, output)
+    }
+}
+
 #[derive(Options)]
 /// Print the languages and transformations available in the tool.
 #[expect(clippy::empty_structs_with_brackets, reason = "Options won't derive a unit struct")]
@@ -415,6 +995,21 @@ impl DoIt for ShowInformation {
                 writeln!(output, "{from} 🡺 {to}")?;
                 if let Some(list) = family.transformation_set_contents(&from, &to)? {
                     writeln!(output, "  set of {}", list.join(", "))?
+                } else if let Some(steps) = family.transformation_chain_contents(&from, &to)? {
+                    writeln!(output, "  chain of {}", steps.iter().map(|(step_from, step_name)| format!("{step_from} 🡺 {step_name}")).collect::<Vec<_>>().join(", "))?
+                }
+            }
+        }
+
+        let mut timelines = family.timeline_keys();
+        if !timelines.is_empty() {
+            timelines.sort();
+            writeln!(output)?;
+            writeln!(output, "TIMELINES:")?;
+            for name in timelines {
+                writeln!(output, "{name}")?;
+                for (date, from, transformation) in family.timeline_stages(&name)? {
+                    writeln!(output, "  {date}: {from} 🡺 {transformation}")?;
                 }
             }
         }
@@ -483,8 +1078,19 @@ impl DoIt for FamilyShowUsage {
                 "validate" => show_usage::<ValidateWords>(program, Some(command), output)?,
                 "phonemes" => show_usage::<ShowPhonemes>(program, Some(command), output)?,
                 "spelling" => show_usage::<ShowSpelling>(program, Some(command), output)?,
+                "pronunciation-guide" => show_usage::<PronunciationGuide>(program, Some(command), output)?,
                 "lexicon" => show_usage::<FormatLexicon>(program, Some(command), output)?,
+                "orthography-check" => show_usage::<OrthographyCheck>(program, Some(command), output)?,
+                "spell-text" => show_usage::<SpellText>(program, Some(command), output)?,
                 "transform" => show_usage::<Transform>(program, Some(command), output)?,
+                "transform-stats" => show_usage::<TransformStats>(program, Some(command), output)?,
+                "check-transformations" => show_usage::<CheckTransformations>(program, Some(command), output)?,
+                "ancestors" => show_usage::<Ancestors>(program, Some(command), output)?,
+                "timeline" => show_usage::<ShowTimeline>(program, Some(command), output)?,
+                "cognates" => show_usage::<Cognates>(program, Some(command), output)?,
+                "tree" => show_usage::<ShowFamilyTree>(program, Some(command), output)?,
+                "adapt" => show_usage::<AdaptWords>(program, Some(command), output)?,
+                "correspondences" => show_usage::<Correspondences>(program, Some(command), output)?,
                 "information" => show_usage::<ShowInformation>(program, Some(command), output)?,
                 "help" => show_usage::<Self>(program, Some(command), output)?,
                 command => {
@@ -512,10 +1118,32 @@ pub enum FamilyCommand {
     Phonemes(ShowPhonemes),
     /// Prints out a table of orthographies for a language.
     Spelling(ShowSpelling),
+    /// Prints out a pronunciation guide for a language, for readers who don't know IPA.
+    PronunciationGuide(PronunciationGuide),
     /// Loads a lexicon of words in CSV format for a language, validates them and prints out a formatted listing.
     Lexicon(FormatLexicon),
+    /// Finds distinct words which are spelled the same in an orthography.
+    OrthographyCheck(OrthographyCheck),
+    /// Spells a phonemic text in the orthographies of a language.
+    SpellText(SpellText),
     /// Transforms words from a source language to another.
     Transform(Transform),
+    /// Reports how each rule in a transformation affects a list of words.
+    TransformStats(TransformStats),
+    /// Checks the examples added to transformations and their rules.
+    CheckTransformations(CheckTransformations),
+    /// Searches for words in the source language which transform into the specified words.
+    Ancestors(Ancestors),
+    /// Shows the forms of words at dates in a timeline of sound changes.
+    Timeline(ShowTimeline),
+    /// Shows what each word in a lexicon becomes in every language descended from the source language.
+    Cognates(Cognates),
+    /// Shows the languages in the family, with the transformations between them.
+    Tree(ShowFamilyTree),
+    /// Adapts loanwords from the source language into another language.
+    Adapt(AdaptWords),
+    /// Finds the sound correspondences between the source language and another language from pairs of cognates.
+    Correspondences(Correspondences),
     /// Print the information about the available languages.
     Information(ShowInformation),
     /// Run analysis on word inpuy to help you build phonotactics
@@ -537,8 +1165,19 @@ impl DoIt for FamilyCommand {
             Self::Validate(command) => command.doit(family, language, output),
             Self::Phonemes(command) => command.doit(family, language, output),
             Self::Spelling(command) => command.doit(family, language, output),
+            Self::PronunciationGuide(command) => command.doit(family, language, output),
             Self::Lexicon(command) => command.doit(family, language, output),
+            Self::OrthographyCheck(command) => command.doit(family, language, output),
+            Self::SpellText(command) => command.doit(family, language, output),
             Self::Transform(command) => command.doit(family, language, output),
+            Self::TransformStats(command) => command.doit(family, language, output),
+            Self::CheckTransformations(command) => command.doit(family, language, output),
+            Self::Ancestors(command) => command.doit(family, language, output),
+            Self::Timeline(command) => command.doit(family, language, output),
+            Self::Cognates(command) => command.doit(family, language, output),
+            Self::Tree(command) => command.doit(family, language, output),
+            Self::Adapt(command) => command.doit(family, language, output),
+            Self::Correspondences(command) => command.doit(family, language, output),
             Self::Information(command) => command.doit(family, language, output),
             Self::Analyze(command) => command.doit(family, language, output),
             Self::Help(command) => command.doit(family, language, output)
@@ -583,7 +1222,10 @@ impl DoIt for LanguageShowUsage {
                 "validate" => show_usage::<ValidateWords>(program, Some(command), output)?,
                 "phonemes" => show_usage::<ShowPhonemes>(program, Some(command), output)?,
                 "spelling" => show_usage::<ShowSpelling>(program, Some(command), output)?,
+                "pronunciation-guide" => show_usage::<PronunciationGuide>(program, Some(command), output)?,
                 "lexicon" => show_usage::<FormatLexicon>(program, Some(command), output)?,
+                "orthography-check" => show_usage::<OrthographyCheck>(program, Some(command), output)?,
+                "spell-text" => show_usage::<SpellText>(program, Some(command), output)?,
                 "help" => show_usage::<Self>(program, Some(command), output)?,
                 command => {
                     eprintln!("Unknown command '{command}'");
@@ -610,8 +1252,14 @@ pub enum LanguageCommand {
     Phonemes(ShowPhonemes),
     /// Prints out a table of orthographies for a language.
     Spelling(ShowSpelling),
+    /// Prints out a pronunciation guide for a language, for readers who don't know IPA.
+    PronunciationGuide(PronunciationGuide),
     /// Loads a lexicon of words in CSV format for a language, validates them and prints out a formatted listing.
     Lexicon(FormatLexicon),
+    /// Finds distinct words which are spelled the same in an orthography.
+    OrthographyCheck(OrthographyCheck),
+    /// Spells a phonemic text in the orthographies of a language.
+    SpellText(SpellText),
     /// Print out this information. Use 'help COMMAND' to get help on a specific command.
     Help(LanguageShowUsage)
 }
@@ -629,7 +1277,10 @@ impl DoIt for LanguageCommand {
             Self::Validate(command) => command.doit(family, language, output),
             Self::Phonemes(command) => command.doit(family, language, output),
             Self::Spelling(command) => command.doit(family, language, output),
+            Self::PronunciationGuide(command) => command.doit(family, language, output),
             Self::Lexicon(command) => command.doit(family, language, output),
+            Self::OrthographyCheck(command) => command.doit(family, language, output),
+            Self::SpellText(command) => command.doit(family, language, output),
             Self::Help(command) => command.doit(family, language, output)
         }
     }
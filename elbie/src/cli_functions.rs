@@ -1,23 +1,40 @@
+use crate::adaptation::MAX_REPAIR_COST;
+use crate::adaptation::adapt_word;
 use crate::analysis::AnalysisConfig;
+use crate::correspondence::align_cognates;
+use crate::correspondence::count_correspondences;
 use crate::errors::ElbieError;
+use crate::family::transform_and_validate_word;
+use crate::family::transform_with_set;
+use crate::family::validate_word;
 use crate::format::Format;
 use crate::grid::Cell;
+use crate::grid::ColumnHeader;
 use crate::grid::Grid;
 use crate::grid::GridRow;
 use crate::grid::TRBodyClass;
 use crate::grid::TableClass;
 use crate::language::Language;
 use crate::lexicon::LexiconStyle;
+use crate::phoneme::Phoneme;
+use crate::text::spell_text;
 use crate::transformation::PreparedTransformation;
+use crate::transformation::TimelineStage;
 use crate::transformation::Transformation;
 use crate::transformation::TransformationTraceCallback;
 use crate::validation::ValidationTraceCallback;
 use crate::word::Word;
 use crate::word_table::WordTable;
+use core::convert::Infallible;
 use core::error::Error;
-use core::num::ParseIntError;
+use core::slice;
 use core::str::FromStr;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::io::Write;
+use std::rc::Rc;
 
 pub(crate) enum ValidateOption {
     Simple,
@@ -33,9 +50,12 @@ pub(crate) enum TransformationOption {
     ExplainAndTrace
 }
 
-pub(crate) fn generate_words(grid_style: Option<&Format>, language: &Language, count: usize, output: &mut impl Write) -> Result<(), Box<dyn Error>> {
+pub(crate) fn generate_words(grid_style: Option<&Format>, language: &Language, count: usize, phonetic: bool, output: &mut impl Write) -> Result<(), Box<dyn Error>> {
     let mut grid = Grid::new(TableClass::ElbieWords, format!("Generated {count} words for {}", language.name()));
 
+    // without allophonic rules, there's no phonetic transcription to show.
+    let phonetic = phonetic && language.allophony().is_some();
+
     // FUTURE: Should I have a header?
 
     for _ in 0..count {
@@ -44,9 +64,12 @@ pub(crate) fn generate_words(grid_style: Option<&Format>, language: &Language, c
         let word = language.make_word()?;
 
         for orthography in 0..language.orthographies().len() {
-            row.push_cell(Cell::content(language.spell_word(&word, orthography), None));
+            row.push_cell(language.spelling_cell(&mut grid, orthography, language.spell_word(&word, orthography)?));
         }
         row.push_cell(Cell::content(format!("{word}"), None));
+        if phonetic {
+            row.push_cell(Cell::content(language.phonetic_string(&word)?, None));
+        }
 
         // the following is a sanity check. It might catch some logic errors, but really it's just GIGO.
         if matches!(language.check_word(&word, None /* eat message, no need to report */)?, Err(())) {
@@ -59,24 +82,9 @@ pub(crate) fn generate_words(grid_style: Option<&Format>, language: &Language, c
     Ok(())
 }
 
-pub(crate) fn validate_word(language: &Language, word: &Word, explain: bool, trace_cb: Option<&ValidationTraceCallback>) -> Result<Result<(), ()>, ElbieError> {
-    match language.check_word(word, trace_cb)? {
-        Err(()) => Ok(Err(())),
-        Ok(validated) => {
-            if explain {
-                eprintln!("Explain: {word}");
-                for valid in validated {
-                    eprintln!("{valid}")
-                }
-            }
-
-            Ok(Ok(()))
-        }
-    }
-}
-
-pub(crate) fn validate_words(language: &Language, mut words: WordTable, option: &ValidateOption, output_format: &Format, output: &mut impl Write) -> Result<(), Box<dyn Error>> {
+pub(crate) fn validate_words(language: &Language, mut words: WordTable, option: &ValidateOption, phonetic: bool, output_format: &Format, output: &mut impl Write) -> Result<(), Box<dyn Error>> {
     const VALIDATED_ATTR: &str = "Validated";
+    const PHONETIC_ATTR: &str = "Phonetic";
 
     let mut invalid_count = 0;
     let trace_cb: Option<&ValidationTraceCallback> = if matches!(option, ValidateOption::Trace | ValidateOption::ExplainAndTrace) {
@@ -87,8 +95,13 @@ pub(crate) fn validate_words(language: &Language, mut words: WordTable, option:
         None
     };
 
-    for orthography in language.orthographies() {
-        words.add_attribute((*orthography).to_owned());
+    for (i, orthography) in language.orthographies().iter().enumerate() {
+        words.add_spelling_attribute((*orthography).to_owned(), language.script(i));
+    }
+    // without allophonic rules, there's no phonetic transcription to show.
+    let phonetic = phonetic && language.allophony().is_some();
+    if phonetic {
+        words.add_attribute(PHONETIC_ATTR.to_owned());
     }
     words.add_attribute(VALIDATED_ATTR.to_owned());
 
@@ -101,7 +114,10 @@ pub(crate) fn validate_words(language: &Language, mut words: WordTable, option:
                     Ok(Ok(())) => {
                         entry.set_attribute(VALIDATED_ATTR.to_owned(), "Valid".to_owned());
                         for (i, orthography) in language.orthographies().iter().enumerate() {
-                            entry.set_attribute((*orthography).to_owned(), language.spell_word(&word, i));
+                            entry.set_attribute((*orthography).to_owned(), language.spell_word(&word, i)?);
+                        }
+                        if phonetic {
+                            entry.set_attribute(PHONETIC_ATTR.to_owned(), language.phonetic_string(&word)?);
                         }
                     },
                     Ok(Err(())) => {
@@ -149,59 +165,86 @@ pub(crate) fn show_phonemes(grid_style: Option<&Format>, language: &Language, ta
     Ok(())
 }
 
-pub(crate) fn show_spelling(grid_style: Option<&Format>, language: &Language, columns: usize, output: &mut impl Write) -> Result<(), Box<dyn Error>> {
+pub(crate) fn show_spelling(grid_style: Option<&Format>, language: &Language, columns: usize, glyphs: bool, output: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let style = grid_style.unwrap_or(&Format::Terminal { spans: false });
     let grid = language.display_spelling(columns)?;
-    grid.into_output(grid_style.unwrap_or(&Format::Terminal { spans: false })).print(output)?;
+    grid.into_output(style).print(output)?;
+    if glyphs {
+        for orthography in 0..language.orthographies().len() {
+            if let Some(glyph_grid) = language.display_glyphs(orthography)? {
+                writeln!(output)?;
+                glyph_grid.into_output(style).print(output)?;
+            }
+        }
+    }
     Ok(())
 }
 
-pub(crate) fn format_lexicon(format: &Format, style: &LexiconStyle, language: &Language, path: &WordTable, ortho_index: usize, output: &mut impl Write) -> Result<(), Box<dyn Error>> {
-    if ortho_index >= language.orthographies().len() {
-        panic!("Language only has {} orthographies.", language.orthographies().len())
-    }
+pub(crate) fn show_pronunciation_guide(grid_style: &Format, language: &Language, output: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let grid = language.display_pronunciation_guide()?;
+    grid.into_output(grid_style).print(output)?;
+    Ok(())
+}
+
+pub(crate) fn format_lexicon(format: &Format, style: &LexiconStyle, language: &Language, path: &WordTable, spellings: &[OrthographyIndex], phonetic: bool, output: &mut impl Write)
+                             -> Result<(), Box<dyn Error>> {
+    let primary_orthographies = OrthographyIndex::calculate_orthographies(spellings, language)?.into_iter().map(|(i, _)| i).collect();
 
-    let lexicon = language.load_lexicon(path, ortho_index, style)?;
+    let lexicon = language.load_lexicon(path, primary_orthographies, style, phonetic)?;
     lexicon.print(format, output)?;
     Ok(())
 }
 
-pub(crate) fn transform_and_validate_word(word: &Word, transformation: &Transformation, validator: Option<&Language>, explain: bool, transformation_trace_cb: Option<&TransformationTraceCallback>,
-                                          validation_trace_cb: Option<&ValidationTraceCallback>)
-                                          -> Result<(Word, Option<bool>), ElbieError> {
-    let transformed = transformation.transform(word, transformation_trace_cb)?;
-
-    if let Some(validator) = validator {
-        let valid = validate_word(validator, &transformed, explain, validation_trace_cb)?.is_ok();
-        Ok((transformed, Some(valid)))
-    } else {
-        Ok((transformed, None))
-    }
+// Variants in free variation are listed with a tilde, which is how they're usually written.
+fn join_variants<Item: ToString>(variants: &[Item]) -> String {
+    variants.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ~ ")
 }
 
 pub(crate) enum OrthographyIndex {
     Index(usize),
+    Name(String),
     All
 }
 
 impl FromStr for OrthographyIndex {
-    type Err = ParseIntError;
+    type Err = Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // names aren't checked until we know which language they're for.
         match s.parse() {
             Ok(index) => Ok(Self::Index(index)),
             Err(_) if s == "all" => Ok(Self::All),
-            Err(err) => Err(err)
+            Err(_) => Ok(Self::Name(s.to_owned()))
         }
     }
 }
 
 impl OrthographyIndex {
+    // Finds a single orthography in the language, or None if the language doesn't have it.
+    fn find_in(&self, language: &Language) -> Option<usize> {
+        match self {
+            Self::Index(index) => (*index < language.orthographies().len()).then_some(*index),
+            Self::Name(name) => language.find_orthography(name).ok(),
+            Self::All => None
+        }
+    }
+
+    // An error for an orthography that none of the languages have.
+    fn unknown(&self) -> ElbieError {
+        match self {
+            Self::Index(index) => ElbieError::UnknownOrthography(*index),
+            Self::Name(name) => ElbieError::UnknownOrthographyName(name.clone()),
+            Self::All => ElbieError::UnknownOrthographyName("all".to_owned())
+        }
+    }
+
     fn calculate_orthographies(spellings: &[Self], validator: &Language) -> Result<Vec<(usize, &'static str)>, ElbieError> {
         let mut spelling_indexes = Vec::new();
         let orthographies = validator.orthographies();
         for orthography_index in spellings {
             match orthography_index {
                 Self::Index(index) => spelling_indexes.push(*index),
+                Self::Name(name) => spelling_indexes.push(validator.find_orthography(name)?),
                 Self::All => {
                     spelling_indexes = orthographies.iter().enumerate().map(|(i, _)| i).collect();
                     break;
@@ -261,8 +304,8 @@ pub(crate) fn transform_words(from: &Language, transformations: &[PreparedTransf
                            && let Some(validator) = transformation.validator
     {
         let orthographies = OrthographyIndex::calculate_orthographies(spellings, validator)?;
-        for (_, orthography) in &orthographies {
-            words.add_attribute((*orthography).to_owned());
+        for (i, orthography) in &orthographies {
+            words.add_spelling_attribute((*orthography).to_owned(), validator.script(*i));
         }
         Some(orthographies)
     } else if spellings.is_empty() {
@@ -283,38 +326,36 @@ pub(crate) fn transform_words(from: &Language, transformations: &[PreparedTransf
                 // only the last error will be returned if this is a set...
                 let mut last_failure = None;
 
-                for item in transformations {
-                    match transform_and_validate_word(&word,
-                                                      item.transformation,
-                                                      item.validator,
-                                                      matches!(option, TransformationOption::Explain | TransformationOption::ExplainAndTrace),
-                                                      transformation_trace_cb,
-                                                      validation_trace_cb)
-                    {
-                        Ok((transformed, validated)) => {
-                            if replace_word {
-                                // replace that word with the transformed and move the original to a new attribute
-                                entry.replace_word(Some(original_word_attr.to_owned()), transformed.to_string());
-                            } else {
-                                entry.set_attribute(item.name.clone(), transformed.to_string());
-                            }
+                // the attributes are changed as each transformation is added, so the rules only see the original ones.
+                let attributes = entry.attributes().clone();
 
-                            if let Some(validator) = item.validator
-                               && let Some(orthographies) = &orthographies
-                            {
-                                for (i, orthography) in orthographies {
-                                    let spelled = validator.spell_word(&transformed, *i);
-                                    entry.set_attribute((*orthography).to_owned(), spelled);
-                                }
-                            }
+                let results = transform_with_set(&word,
+                                                 &attributes,
+                                                 transformations,
+                                                 matches!(option, TransformationOption::Explain | TransformationOption::ExplainAndTrace),
+                                                 transformation_trace_cb,
+                                                 validation_trace_cb)
+                                                                     // these should be errors in programming the transformation and validator, not just an invalid word.
+                                                                     .map_err(|err| format!("Error transforming and validating word {word}: {err}"))?;
 
-                            last_failure = validated.and_then(|valid| (!valid).then(|| "Word was invalid (see trace)".to_owned()))
-                        },
-                        Err(err) => {
-                            // these should be errors in programming the transformation and validator, not just an invalid word.
-                            return Err(format!("Error transforming and validating word {word}: {err}").into());
+                for (item, result) in transformations.iter().zip(results) {
+                    if replace_word {
+                        // replace that word with the transformed and move the original to a new attribute
+                        entry.replace_word(Some(original_word_attr.to_owned()), join_variants(&result.words));
+                    } else {
+                        entry.set_attribute(item.name.clone(), join_variants(&result.words));
+                    }
+
+                    if let Some(validator) = item.validator
+                       && let Some(orthographies) = &orthographies
+                    {
+                        for (i, orthography) in orthographies {
+                            let spelled = result.words.iter().map(|variant| validator.spell_word(variant, *i)).collect::<Result<Vec<_>, _>>()?;
+                            entry.set_attribute((*orthography).to_owned(), join_variants(&spelled));
                         }
                     }
+
+                    last_failure = result.valid.and_then(|valid| (!valid).then(|| "Word was invalid (see trace)".to_owned()));
                 }
 
                 last_failure
@@ -350,6 +391,734 @@ pub(crate) fn transform_words(from: &Language, transformations: &[PreparedTransf
     }
 }
 
+/// Outputs a derivation table, with a row for each word, and a column for each rule in each transformation showing the word after that rule changed it, with every variant in free variation. The column is blank if the rule didn't change the word. The last column for each transformation is the final result. If there is more than one transformation, the rule columns are prefixed with the transformation name. Returns false if any words were invalid.
+pub(crate) fn derive_words(from: &Language, transformations: &[PreparedTransformation], words: &WordTable, output_format: &Format, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+    let mut headers = vec![ColumnHeader::new(from.name().to_owned(), 1)];
+    for item in transformations {
+        for rule in item.transformation.rule_names() {
+            if transformations.len() == 1 {
+                headers.push(ColumnHeader::new(rule.to_owned(), 1));
+            } else {
+                headers.push(ColumnHeader::new(format!("{}: {rule}", item.name), 1));
+            }
+        }
+        headers.push(ColumnHeader::new(item.name.clone(), 1));
+    }
+    let column_count = headers.len();
+
+    // the error column is only added if there are errors, so collect the rows first.
+    let mut rows = Vec::new();
+    for entry in words.entries() {
+        let mut cells = Vec::new();
+        let mut error = None;
+        match from.read_word(entry.word()) {
+            Ok(word) => {
+                cells.push(word.to_string());
+                // the result of the previous transformation, for chains.
+                let mut previous = vec![word.clone()];
+                for item in transformations {
+                    let start = if item.chained {
+                        previous
+                    } else {
+                        vec![word.clone()]
+                    };
+                    let derivation = item.transformation.derive(&start, entry.attributes()).map_err(|err| format!("Error transforming word {}: {err}", join_variants(&start)))?;
+                    let mut transformed = start;
+                    for (_, changed) in derivation {
+                        if let Some(changed) = changed {
+                            cells.push(join_variants(&changed));
+                            transformed = changed;
+                        } else {
+                            cells.push(String::new());
+                        }
+                    }
+                    cells.push(join_variants(&transformed));
+
+                    if let Some(validator) = item.validator {
+                        for variant in &transformed {
+                            if validate_word(validator, variant, false, None)?.is_err() {
+                                error = Some("Word was invalid".to_owned());
+                            }
+                        }
+                    }
+                    previous = transformed;
+                }
+            },
+            Err(err) => {
+                cells.push(entry.word().clone());
+                cells.resize(column_count, String::new());
+                error = Some(format!("Can't read word: {err}"));
+            }
+        }
+        rows.push((cells, error));
+    }
+
+    let invalid_found = rows.iter().any(|(_, error)| error.is_some());
+    if invalid_found {
+        headers.push(ColumnHeader::new("Error".to_owned(), 1));
+    }
+
+    let mut grid = Grid::new(TableClass::ElbieDerivation, format!("Derivation from {}", from.name()));
+    grid.set_headers(headers);
+
+    for (cells, error) in rows {
+        let mut row = GridRow::new(TRBodyClass::BodyRow);
+        for cell in cells {
+            row.push_cell(Cell::content(cell, None));
+        }
+        if invalid_found {
+            row.push_cell(Cell::content(error.unwrap_or_default(), None));
+        }
+        grid.push_body_row(row);
+    }
+
+    grid.into_output(output_format).print(output)?;
+
+    if invalid_found {
+        eprintln!("!!! Look for errors in Error column.");
+        Ok(false)
+    } else {
+        Ok(true)
+    }
+}
+
+#[derive(Default)]
+struct RuleStats {
+    changed: usize,
+    examples: Vec<String>,
+    ended_invalid: usize,
+    before: BTreeSet<&'static str>,
+    after: BTreeSet<&'static str>
+}
+
+/// Runs the words through each transformation and reports, for every rule, how many words it changed, some examples, how many of those words were invalid at the end of the transformation, and which phonemes the rule added to or removed from the corpus. Words which can't be read are listed after the rules, with the error. Returns false if any rules never changed a word, or any words couldn't be read.
+pub(crate) fn transformation_stats(from: &Language, transformations: &[PreparedTransformation], words: &WordTable, output_format: &Format, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+    const MAX_EXAMPLES: usize = 3;
+
+    let mut corpus = Vec::new();
+    // the error column is only added if there are errors, so collect the rows first.
+    let mut rows: Vec<(Vec<String>, Option<String>)> = Vec::new();
+    let mut unreadable = Vec::new();
+    for entry in words.entries() {
+        match from.read_word(entry.word()) {
+            Ok(word) => corpus.push((word, entry.attributes())),
+            Err(err) => unreadable.push((vec![entry.word().to_owned(), String::new(), String::new(), String::new(), String::new(), String::new()], Some(format!("Can't read word: {err}"))))
+        }
+    }
+
+    let mut unused_count = 0;
+
+    // the results of the previous transformation, for chains.
+    let mut previous_words: Vec<Word> = corpus.iter().map(|(word, _)| word.clone()).collect();
+
+    for item in transformations {
+        let rule_names = item.transformation.rule_names();
+        let mut stats: Vec<RuleStats> = rule_names.iter().map(|_| RuleStats::default()).collect();
+
+        for ((original, attributes), previous_word) in corpus.iter().zip(&mut previous_words) {
+            let word = if item.chained {
+                previous_word.clone()
+            } else {
+                original.clone()
+            };
+            let derivation = item.transformation.derive(slice::from_ref(&word), attributes).map_err(|err| format!("Error transforming word {word}: {err}"))?;
+            // only the first variant is counted, so each word is counted once.
+            let derivation: Vec<_> = derivation.into_iter().map(|(rule, changed)| (rule, changed.and_then(|variants| variants.into_iter().next()))).collect();
+            let transformed = derivation.iter().rev().find_map(|(_, changed)| changed.clone()).unwrap_or_else(|| word.clone());
+            // the forms between rules aren't words of the target language yet, so only the final word is validated.
+            let ended_invalid = if let Some(validator) = item.validator {
+                validate_word(validator, &transformed, false, None)?.is_err()
+            } else {
+                false
+            };
+
+            let mut previous = word.clone();
+            for ((_, changed), rule_stats) in derivation.into_iter().zip(&mut stats) {
+                rule_stats.before.extend(previous.phonemes().iter().map(|phoneme| phoneme.name));
+                if let Some(changed) = changed
+                   && changed != previous
+                {
+                    rule_stats.changed += 1;
+                    if rule_stats.examples.len() < MAX_EXAMPLES {
+                        rule_stats.examples.push(format!("{previous} → {changed}"));
+                    }
+                    if ended_invalid {
+                        rule_stats.ended_invalid += 1;
+                    }
+                    previous = changed;
+                }
+                rule_stats.after.extend(previous.phonemes().iter().map(|phoneme| phoneme.name));
+            }
+            *previous_word = transformed;
+        }
+
+        for (rule, rule_stats) in rule_names.into_iter().zip(stats) {
+            let name = if transformations.len() == 1 {
+                rule.to_owned()
+            } else {
+                format!("{}: {rule}", item.name)
+            };
+            if rule_stats.changed == 0 {
+                unused_count += 1;
+            }
+            let added = rule_stats.after.difference(&rule_stats.before).copied().collect::<Vec<_>>().join(", ");
+            let lost = rule_stats.before.difference(&rule_stats.after).copied().collect::<Vec<_>>().join(", ");
+            rows.push((vec![name, rule_stats.changed.to_string(), rule_stats.examples.join("; "), rule_stats.ended_invalid.to_string(), added, lost], None));
+        }
+    }
+    rows.extend(unreadable);
+
+    let mut headers = vec![ColumnHeader::new("Rule".to_owned(), 1),
+                           ColumnHeader::new("Changed".to_owned(), 1),
+                           ColumnHeader::new("Examples".to_owned(), 1),
+                           ColumnHeader::new("Changed Words Ending Invalid".to_owned(), 1),
+                           ColumnHeader::new("New Phonemes".to_owned(), 1),
+                           ColumnHeader::new("Lost Phonemes".to_owned(), 1)];
+    let error_found = rows.iter().any(|(_, error)| error.is_some());
+    if error_found {
+        headers.push(ColumnHeader::new("Error".to_owned(), 1));
+    }
+
+    let mut grid = Grid::new(TableClass::ElbieTransformStats, format!("Transformation statistics for {} words from {}", corpus.len(), from.name()));
+    grid.set_headers(headers);
+
+    for (cells, error) in rows {
+        let mut row = GridRow::new(TRBodyClass::BodyRow);
+        for cell in cells {
+            row.push_cell(Cell::content(cell, None));
+        }
+        if error_found {
+            row.push_cell(Cell::content(error.unwrap_or_default(), None));
+        }
+        grid.push_body_row(row);
+    }
+
+    grid.into_output(output_format).print(output)?;
+
+    if unused_count > 0 {
+        eprintln!("!!! {unused_count} rules did not change any words.");
+    }
+    if error_found {
+        eprintln!("!!! Look for errors in Error column.");
+    }
+    Ok(unused_count == 0 && !error_found)
+}
+
+// Shows where two words differ, with the rest of the word around it: /kl[i → e]d/
+fn diff_words(expected: &Word, actual: &Word) -> String {
+    let expected = expected.phonemes();
+    let actual = actual.phonemes();
+    let prefix = expected.iter().zip(actual).take_while(|(a, b)| a == b).count();
+    let max_suffix = expected.len().min(actual.len()) - prefix;
+    let suffix = expected.iter().rev().zip(actual.iter().rev()).take(max_suffix).take_while(|(a, b)| a == b).count();
+
+    let raw = |phonemes: &[Rc<Phoneme>]| phonemes.iter().map(|phoneme| phoneme.name).collect::<String>();
+    let start = expected.get(..prefix).map(raw).unwrap_or_default();
+    let expected_middle = expected.get(prefix..expected.len() - suffix).map(raw).unwrap_or_default();
+    let actual_middle = actual.get(prefix..actual.len() - suffix).map(raw).unwrap_or_default();
+    let end = expected.get(expected.len() - suffix..).map(raw).unwrap_or_default();
+    format!("/{start}[{expected_middle} → {actual_middle}]{end}/")
+}
+
+/// Checks the examples added to each transformation, and lists the ones that failed along with a diff of the expected and actual words. Examples which can't be transformed are listed with the error. Returns false if any failed.
+pub(crate) fn check_transformations(transformations: &[(String, &Transformation)], output_format: &Format, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+    let mut headers = vec![ColumnHeader::new("Transformation".to_owned(), 1),
+                           ColumnHeader::new("Rule".to_owned(), 1),
+                           ColumnHeader::new("Input".to_owned(), 1),
+                           ColumnHeader::new("Expected".to_owned(), 1),
+                           ColumnHeader::new("Actual".to_owned(), 1),
+                           ColumnHeader::new("Diff".to_owned(), 1)];
+
+    let mut example_count = 0;
+    // the error column is only added if there are errors, so collect the rows first.
+    let mut rows = Vec::new();
+
+    for (name, transformation) in transformations {
+        for example in transformation.check_examples() {
+            example_count += 1;
+            if !example.passed() {
+                let rule = example.rule.unwrap_or("(all rules)").to_owned();
+                match example.actual {
+                    Ok(actual) => {
+                        // the diff only makes sense if there's one variant of each, otherwise it's hard to say which goes with which.
+                        let diff = if let ([expected], [actual]) = (example.expected.as_slice(), actual.as_slice()) {
+                            diff_words(expected, actual)
+                        } else {
+                            String::new()
+                        };
+                        rows.push((vec![name.clone(), rule, example.input.to_string(), join_variants(&example.expected), join_variants(&actual), diff], None));
+                    },
+                    Err(err) => rows.push((vec![name.clone(), rule, example.input.to_string(), join_variants(&example.expected), String::new(), String::new()], Some(err.to_string())))
+                }
+            }
+        }
+    }
+
+    let failure_count = rows.len();
+    if failure_count > 0 {
+        let error_found = rows.iter().any(|(_, error)| error.is_some());
+        if error_found {
+            headers.push(ColumnHeader::new("Error".to_owned(), 1));
+        }
+
+        let mut grid = Grid::new(TableClass::ElbieExampleCheck, "Failed transformation examples".to_owned());
+        grid.set_headers(headers);
+        for (cells, error) in rows {
+            let mut row = GridRow::new(TRBodyClass::BodyRow);
+            for cell in cells {
+                row.push_cell(Cell::content(cell, None));
+            }
+            if error_found {
+                row.push_cell(Cell::content(error.unwrap_or_default(), None));
+            }
+            grid.push_body_row(row);
+        }
+
+        grid.into_output(output_format).print(output)?;
+        eprintln!("!!! {failure_count} of {example_count} examples failed.");
+        Ok(false)
+    } else {
+        eprintln!("All {example_count} examples passed.");
+        Ok(true)
+    }
+}
+
+/// Lists the words in the source language, up to `max_length` phonemes, which transform into each of the specified words. Returns false if any words had no ancestors.
+pub(crate) fn find_ancestors(from: &Language, transformation: &PreparedTransformation, words: &WordTable, max_length: usize, output_format: &Format, output: &mut impl Write)
+                             -> Result<bool, Box<dyn Error>> {
+    let mut targets = Vec::new();
+    for (row, entry) in words.entries().enumerate() {
+        let word = transformation.transformation.read_word(entry.word()).map_err(|err| format!("Can't read word at row {row}: {err}"))?;
+        targets.push(word);
+    }
+
+    let sources = transformation.transformation.find_sources(from, &targets, max_length)?;
+
+    let mut grid = Grid::new(TableClass::ElbieAncestors, format!("Possible {} ancestors up to {max_length} phonemes", from.name()));
+    grid.set_headers(vec![ColumnHeader::new(transformation.name.clone(), 1), ColumnHeader::new(from.name().to_owned(), 1)]);
+
+    let mut orphan_count = 0;
+    for (word, ancestors) in targets.iter().zip(sources) {
+        if ancestors.is_empty() {
+            orphan_count += 1;
+        }
+        let mut row = GridRow::new(TRBodyClass::BodyRow);
+        row.push_cell(Cell::content(word.to_string(), None));
+        row.push_cell(Cell::content(ancestors.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "), None));
+        grid.push_body_row(row);
+    }
+
+    grid.into_output(output_format).print(output)?;
+
+    if orphan_count > 0 {
+        eprintln!("!!! No ancestors were found for {orphan_count} words. Try a longer length.");
+        Ok(false)
+    } else {
+        Ok(true)
+    }
+}
+
+// Runs the words through every stage of the timeline after `entered` and up to `date`. If a result was invalid, the later stages are still applied, and the first error is returned with the final variants.
+fn apply_timeline(stages: &[TimelineStage], word: &Word, attributes: &HashMap<String, String>, entered: Option<i64>, date: i64) -> Result<(Vec<Word>, Option<String>), ElbieError> {
+    let mut words = vec![word.clone()];
+    let mut error = None;
+    for stage in stages.iter().filter(|stage| entered.is_none_or(|entered| stage.date > entered) && stage.date <= date) {
+        for item in &stage.transformations {
+            let (transformed, valid) = transform_and_validate_word(&words, attributes, item.transformation, item.validator, false, None, None)?;
+            words = transformed;
+            if valid == Some(false) && error.is_none() {
+                error = Some(format!("Invalid result from {} at {}", item.name, stage.date));
+            }
+        }
+    }
+    Ok((words, error))
+}
+
+/// Prints out the form of each word at each of the requested dates in the timeline, or at each stage if no dates are requested. Words with a date in `date_attribute` entered the language at that date, and aren't changed by any earlier stages. Words which were invalid at some stage are still shown at the later dates, with the first error. Returns false if any results were invalid, or any rows couldn't be read.
+pub(crate) fn transform_timeline(stages: &[TimelineStage], words: &WordTable, dates: &[i64], date_attribute: &str, output_format: &Format, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+    let dates = if dates.is_empty() {
+        stages.iter().map(|stage| stage.date).collect()
+    } else {
+        dates.to_vec()
+    };
+    let column_count = dates.len() + 2;
+
+    let mut rows = Vec::new();
+    let mut errors_found = false;
+    for entry in words.entries() {
+        let entered_text = entry.get_attribute(&date_attribute.to_owned()).map(|date| date.trim()).filter(|date| !date.is_empty());
+        let entered = match entered_text.map(str::parse::<i64>).transpose() {
+            Ok(entered) => entered,
+            Err(err) => {
+                let mut cells = vec![entry.word().clone(), entered_text.unwrap_or_default().to_owned()];
+                cells.resize(column_count, String::new());
+                rows.push((cells, Some(format!("Can't read date: {err}"))));
+                errors_found = true;
+                continue;
+            }
+        };
+
+        // the word is written in the language it entered, which is the source of the first stage after it entered.
+        let reader = stages.iter().find(|stage| entered.is_none_or(|entered| stage.date > entered));
+        let word = match reader {
+            Some(stage) => stage.language.read_word(entry.word()),
+            None => match stages.last().and_then(|stage| stage.transformations.last()) {
+                Some(item) => item.transformation.read_word(entry.word()),
+                None => return Err("The timeline has no stages.".into())
+            }
+        };
+        let word = match word {
+            Ok(word) => word,
+            Err(err) => {
+                let mut cells = vec![entry.word().clone(), entered.map(|entered| entered.to_string()).unwrap_or_default()];
+                cells.resize(column_count, String::new());
+                rows.push((cells, Some(format!("Can't read word: {err}"))));
+                errors_found = true;
+                continue;
+            }
+        };
+
+        let mut cells = vec![word.to_string(), entered.map(|entered| entered.to_string()).unwrap_or_default()];
+        let mut first_error = None;
+        for date in &dates {
+            if entered.is_some_and(|entered| entered > *date) {
+                // it wasn't part of the language yet.
+                cells.push(String::new());
+            } else {
+                match apply_timeline(stages, &word, entry.attributes(), entered, *date) {
+                    Ok((variants, error)) => {
+                        cells.push(join_variants(&variants));
+                        if first_error.is_none() {
+                            first_error = error;
+                        }
+                    },
+                    Err(err) => {
+                        // every later date goes through the same stages, so they'd fail too.
+                        cells.resize(column_count, String::new());
+                        first_error = Some(format!("Error transforming word: {err}"));
+                        break;
+                    }
+                }
+            }
+        }
+        errors_found = errors_found || first_error.is_some();
+        rows.push((cells, first_error));
+    }
+
+    let mut grid = Grid::new(TableClass::ElbieTimeline, "Timeline".to_owned());
+    let mut headers = vec![ColumnHeader::new("Word".to_owned(), 1), ColumnHeader::new("Entered".to_owned(), 1)];
+    headers.extend(dates.iter().map(|date| ColumnHeader::new(date.to_string(), 1)));
+    if errors_found {
+        headers.push(ColumnHeader::new("Error".to_owned(), 1));
+    }
+    grid.set_headers(headers);
+
+    for (cells, error) in rows {
+        let mut row = GridRow::new(TRBodyClass::BodyRow);
+        for cell in cells {
+            row.push_cell(Cell::content(cell, None));
+        }
+        if errors_found {
+            row.push_cell(Cell::content(error.unwrap_or_default(), None));
+        }
+        grid.push_body_row(row);
+    }
+
+    grid.into_output(output_format).print(output)?;
+
+    if errors_found {
+        eprintln!("!!! Look for errors in Error column.");
+        Ok(false)
+    } else {
+        Ok(true)
+    }
+}
+
+// Spells the variants in the orthography if the language has it, otherwise they're shown in phonemic form. The marker goes in front of the text.
+fn cognate_cell(grid: &mut Grid, variants: &[Word], language: Option<&Language>, spelling: Option<&OrthographyIndex>, marker: &str) -> Result<Cell, ElbieError> {
+    if let Some(language) = language
+       && let Some(orthography) = spelling.and_then(|spelling| spelling.find_in(language))
+    {
+        let spelled = variants.iter().map(|variant| language.spell_word(variant, orthography)).collect::<Result<Vec<_>, _>>()?;
+        Ok(language.spelling_cell(grid, orthography, format!("{marker}{}", join_variants(&spelled))))
+    } else {
+        Ok(Cell::content(format!("{marker}{}", join_variants(variants)), None))
+    }
+}
+
+/// Prints a table of the words with what they become in each of the descendant transformations. Each descendant comes with the index of the one it transforms the results of, or None if it transforms the original words. Invalid results are marked with "!!". Words which can't be read or transformed are listed with the error, and the descendants of a failed transformation are left blank. Returns false if any results were invalid, or any words had errors. Returns an error if the spelling isn't an orthography of the source language or any of the descendants.
+pub(crate) fn cognate_table(from: &Language, descendants: &[(PreparedTransformation, Option<usize>)], words: &WordTable, spelling: Option<&OrthographyIndex>, output_format: &Format,
+                            output: &mut impl Write)
+                            -> Result<bool, Box<dyn Error>> {
+    if let Some(spelling) = spelling {
+        let found = spelling.find_in(from).is_some() || descendants.iter().filter_map(|(item, _)| item.validator).any(|language| spelling.find_in(language).is_some());
+        if !found {
+            return Err(spelling.unknown().into());
+        }
+    }
+
+    let mut grid = Grid::new(TableClass::ElbieCognates, format!("Cognates of {} words", from.name()));
+    let mut headers = vec![ColumnHeader::new(from.name().to_owned(), 1)];
+    headers.extend(descendants.iter().map(|(item, _)| ColumnHeader::new(item.name.clone(), 1)));
+
+    let mut invalid_count = 0;
+    // the error column is only added if there are errors, so collect the rows first.
+    let mut rows = Vec::new();
+    for entry in words.entries() {
+        let mut cells = GridRow::new(TRBodyClass::BodyRow);
+        let word = match from.read_word(entry.word()) {
+            Ok(word) => word,
+            Err(err) => {
+                cells.push_cell(Cell::content(entry.word().clone(), None));
+                for _ in descendants {
+                    cells.push_cell(Cell::content(String::new(), None));
+                }
+                rows.push((cells, Some(format!("Can't read word: {err}"))));
+                continue;
+            }
+        };
+        cells.push_cell(cognate_cell(&mut grid, slice::from_ref(&word), Some(from), spelling, "")?);
+
+        let mut error = None;
+        // a failed transformation has no results, so its descendants are blank.
+        let mut results: Vec<Vec<Word>> = Vec::new();
+        for (item, parent) in descendants {
+            let inputs = match parent {
+                Some(parent) => results.get(*parent).cloned().unwrap_or_default(),
+                None => vec![word.clone()]
+            };
+            if inputs.is_empty() {
+                cells.push_cell(Cell::content(String::new(), None));
+                results.push(Vec::new());
+                continue;
+            }
+            match transform_and_validate_word(&inputs, entry.attributes(), item.transformation, item.validator, false, None, None) {
+                Ok((transformed, valid)) => {
+                    let marker = if valid == Some(false) {
+                        invalid_count += 1;
+                        "!! "
+                    } else {
+                        ""
+                    };
+                    cells.push_cell(cognate_cell(&mut grid, &transformed, item.validator, spelling, marker)?);
+                    results.push(transformed);
+                },
+                Err(err) => {
+                    cells.push_cell(Cell::content(String::new(), None));
+                    results.push(Vec::new());
+                    if error.is_none() {
+                        error = Some(format!("Error transforming with {}: {err}", item.name));
+                    }
+                }
+            }
+        }
+        rows.push((cells, error));
+    }
+
+    let error_count = rows.iter().filter(|(_, error)| error.is_some()).count();
+    if error_count > 0 {
+        headers.push(ColumnHeader::new("Error".to_owned(), 1));
+    }
+    grid.set_headers(headers);
+
+    for (mut cells, error) in rows {
+        if error_count > 0 {
+            cells.push_cell(Cell::content(error.unwrap_or_default(), None));
+        }
+        grid.push_body_row(cells);
+    }
+
+    grid.into_output(output_format).print(output)?;
+
+    if error_count > 0 {
+        eprintln!("!!! Look for errors in Error column.");
+    }
+    if invalid_count > 0 {
+        eprintln!("!!! {invalid_count} invalid results found.");
+    }
+    Ok(invalid_count == 0 && error_count == 0)
+}
+
+/// Adapts the words from one language into another, showing the closest phonemes and the repaired results. Returns false if any words couldn't be adapted.
+pub(crate) fn adapt_words(from: &Language, to: &Language, words: &WordTable, output_format: &Format, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+    let mut grid = Grid::new(TableClass::ElbieAdaptation, format!("Words adapted from {} to {}", from.name(), to.name()));
+    grid.set_headers(vec![ColumnHeader::new(from.name().to_owned(), 1),
+                          ColumnHeader::new("Closest Phonemes".to_owned(), 1),
+                          ColumnHeader::new(to.name().to_owned(), 1),
+                          ColumnHeader::new("Cost".to_owned(), 1)]);
+
+    let mut failed_count = 0;
+    for (row, entry) in words.entries().enumerate() {
+        let word = from.read_word(entry.word()).map_err(|err| format!("Can't read word at row {row}: {err}"))?;
+        let adaptation = adapt_word(from, to, &word)?;
+
+        let mut cells = GridRow::new(TRBodyClass::BodyRow);
+        cells.push_cell(Cell::content(word.to_string(), None));
+        cells.push_cell(Cell::content(adaptation.mapped.to_string(), None));
+        if let Some(cost) = adaptation.cost {
+            cells.push_cell(Cell::content(join_variants(&adaptation.adapted), None));
+            cells.push_cell(Cell::content(cost.to_string(), None));
+        } else {
+            failed_count += 1;
+            cells.push_cell(Cell::content("!! Not found".to_owned(), None));
+            cells.push_cell(Cell::content(String::new(), None));
+        }
+        grid.push_body_row(cells);
+    }
+
+    grid.into_output(output_format).print(output)?;
+
+    if failed_count > 0 {
+        eprintln!("!!! {failed_count} words couldn't be adapted with a cost of {MAX_REPAIR_COST} or less.");
+        Ok(false)
+    } else {
+        Ok(true)
+    }
+}
+
+/// Aligns pairs of cognates and shows the correspondences between their phonemes which are found in at least `min_count` pairs. Returns false if any pairs have correspondences which aren't regular, and lists those pairs.
+pub(crate) fn sound_correspondences(from: &Language, to: &Language, pairs: &[(String, String)], min_count: usize, output_format: &Format, output: &mut impl Write) -> Result<bool, Box<dyn Error>> {
+    let mut words = Vec::new();
+    for (row, (source, target)) in pairs.iter().enumerate() {
+        let source = from.read_word(source).map_err(|err| format!("Can't read {} word at row {row}: {err}", from.name()))?;
+        let target = to.read_word(target).map_err(|err| format!("Can't read {} word at row {row}: {err}", to.name()))?;
+        words.push((source, target));
+    }
+
+    let cognates = align_cognates(from, to, &words);
+    let correspondences = count_correspondences(&cognates);
+
+    let mut regular_grid = Grid::new(TableClass::ElbieCorrespondences, format!("Regular correspondences from {} to {}", from.name(), to.name()));
+    regular_grid.set_headers(vec![ColumnHeader::new(from.name().to_owned(), 1),
+                                  ColumnHeader::new(to.name().to_owned(), 1),
+                                  ColumnHeader::new("Count".to_owned(), 1),
+                                  ColumnHeader::new("Examples".to_owned(), 1)]);
+
+    for ((source, target), found) in &correspondences {
+        if found.len() < min_count {
+            continue;
+        }
+        let examples = found.iter().take(3).filter_map(|i| cognates.get(*i)).map(|pair| format!("{} : {}", pair.source, pair.target)).collect::<Vec<_>>().join(", ");
+        let mut cells = GridRow::new(TRBodyClass::BodyRow);
+        cells.push_cell(Cell::content(source.to_string(), None));
+        cells.push_cell(Cell::content(target.to_string(), None));
+        cells.push_cell(Cell::content(found.len().to_string(), None));
+        cells.push_cell(Cell::content(examples, None));
+        regular_grid.push_body_row(cells);
+    }
+
+    regular_grid.into_output(output_format).print(output)?;
+
+    let mut irregular_grid = Grid::new(TableClass::ElbieIrregularCognates, "Irregular cognates".to_owned());
+    irregular_grid.set_headers(vec![ColumnHeader::new(from.name().to_owned(), 1),
+                                    ColumnHeader::new(to.name().to_owned(), 1),
+                                    ColumnHeader::new("Alignment".to_owned(), 1),
+                                    ColumnHeader::new("Irregular".to_owned(), 1)]);
+
+    let mut irregular_count = 0;
+    for (i, pair) in cognates.iter().enumerate() {
+        let irregular = correspondences.iter().filter(|(_, found)| found.len() < min_count && found.contains(&i)).map(|((source, target), _)| format!("{source}:{target}")).collect::<Vec<_>>();
+        if irregular.is_empty() {
+            continue;
+        }
+        irregular_count += 1;
+        let mut cells = GridRow::new(TRBodyClass::BodyRow);
+        cells.push_cell(Cell::content(pair.source.to_string(), None));
+        cells.push_cell(Cell::content(pair.target.to_string(), None));
+        cells.push_cell(Cell::content(pair.alignment_string(), None));
+        cells.push_cell(Cell::content(irregular.join(" "), None));
+        irregular_grid.push_body_row(cells);
+    }
+
+    if irregular_count > 0 {
+        irregular_grid.into_output(output_format).print(output)?;
+        eprintln!("!!! {irregular_count} pairs of cognates have irregular correspondences.");
+        Ok(false)
+    } else {
+        Ok(true)
+    }
+}
+
+/// Looks for distinct words which are spelled the same in an orthography. The words come from the supplied table, plus every valid word up to `max_length` phonemes if that is specified. Returns false if any collisions were found.
+pub(crate) fn check_orthography(language: &Language, words: &WordTable, max_length: Option<usize>, spellings: &[OrthographyIndex], output_format: &Format, output: &mut impl Write)
+                                -> Result<bool, Box<dyn Error>> {
+    let mut seen = HashSet::new();
+    let mut candidates = Vec::new();
+
+    for (row, entry) in words.entries().enumerate() {
+        let word = language.read_word(entry.word()).map_err(|err| format!("Can't read word at row {row}: {err}"))?;
+        if seen.insert(word.clone()) {
+            candidates.push(word);
+        }
+    }
+
+    if let Some(max_length) = max_length {
+        for word in language.enumerate_words(max_length)? {
+            if seen.insert(word.clone()) {
+                candidates.push(word);
+            }
+        }
+    }
+
+    let orthographies = if spellings.is_empty() {
+        OrthographyIndex::calculate_orthographies(&[OrthographyIndex::All], language)?
+    } else {
+        OrthographyIndex::calculate_orthographies(spellings, language)?
+    };
+
+    let mut grid = Grid::new(TableClass::ElbieCollisions, format!("Spelling collisions for {}", language.name()));
+    grid.set_headers(vec![ColumnHeader::new("Orthography".to_owned(), 1), ColumnHeader::new("Spelling".to_owned(), 1), ColumnHeader::new("Words".to_owned(), 1)]);
+
+    let mut collision_count = 0;
+
+    for (index, orthography) in orthographies {
+        // BTreeMap so the output is sorted by spelling.
+        let mut spelled: BTreeMap<String, Vec<&Word>> = BTreeMap::new();
+        for word in &candidates {
+            spelled.entry(language.spell_word(word, index)?).or_default().push(word);
+        }
+
+        for (spelling, spelled_words) in spelled {
+            if spelled_words.len() > 1 {
+                collision_count += 1;
+                let mut row = GridRow::new(TRBodyClass::BodyRow);
+                row.push_cell(Cell::content(orthography.to_owned(), None));
+                row.push_cell(Cell::content(spelling, None));
+                row.push_cell(Cell::content(spelled_words.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "), None));
+                grid.push_body_row(row);
+            }
+        }
+    }
+
+    if collision_count > 0 {
+        grid.into_output(output_format).print(output)?;
+        eprintln!("!!! {collision_count} spellings are shared by more than one word (checked {} words).", candidates.len());
+        Ok(false)
+    } else {
+        eprintln!("No spelling collisions found (checked {} words).", candidates.len());
+        Ok(true)
+    }
+}
+
+pub(crate) fn spell_texts(language: &Language, text: &str, spellings: &[OrthographyIndex], output: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let orthographies = if spellings.is_empty() {
+        OrthographyIndex::calculate_orthographies(&[OrthographyIndex::All], language)?
+    } else {
+        OrthographyIndex::calculate_orthographies(spellings, language)?
+    };
+
+    if let [(index, _)] = orthographies.as_slice() {
+        write!(output, "{}", spell_text(language, text, *index)?)?;
+    } else {
+        for (index, orthography) in orthographies {
+            writeln!(output, "{orthography}:")?;
+            writeln!(output, "{}", spell_text(language, text, index)?)?;
+        }
+    }
+
+    Ok(())
+}
+
 pub(crate) fn analyze_words(from: &Language, words: &WordTable, output: &mut impl Write) -> Result<(), Box<dyn Error>> {
     // TODO: Should be able to set up custom analysis stuff on the language itself.
     let config = AnalysisConfig::from_language(from);
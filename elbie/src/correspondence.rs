@@ -0,0 +1,184 @@
+use crate::analysis::AnalysisConfig;
+use crate::language::Language;
+use crate::phoneme::Phoneme;
+use crate::word::Word;
+use core::error::Error;
+use core::fmt;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use csv::Reader;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::rc::Rc;
+
+/*
+NOTE: Sound correspondences are found by aligning each pair of cognates phoneme by phoneme, using the lowest cost alignment:
+- matching a phoneme with one of the same name costs nothing.
+- matching two different phonemes costs up to `MAX_SUBSTITUTION_COST`, depending on how many of their sets are different. If they're in different cluster sets (see `analysis.rs`, usually this means a consonant and a vowel), that costs another `CLUSTER_PENALTY`.
+- matching a phoneme with nothing (it was lost or inserted) costs `GAP_COST`.
+
+Every aligned pair of phonemes is a correspondence. A correspondence which is found at least a minimum number of times is regular, otherwise it's irregular, and the cognates it was found in are flagged. An irregular correspondence might be a sporadic change, a loanword, or a mistake in the cognates, or there might just not be enough examples yet.
+*/
+
+const MAX_SUBSTITUTION_COST: usize = 10;
+const CLUSTER_PENALTY: usize = 10;
+const GAP_COST: usize = 7;
+
+/// One side of a correspondence, None means the phoneme was lost or inserted.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Segment(Option<&'static str>);
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.unwrap_or("∅"))
+    }
+}
+
+pub(crate) struct Cognates {
+    pub source: Word,
+    pub target: Word,
+    pub alignment: Vec<(Segment, Segment)>
+}
+
+impl Cognates {
+    pub(crate) fn alignment_string(&self) -> String {
+        self.alignment.iter().map(|(source, target)| format!("{source}:{target}")).collect::<Vec<_>>().join(" ")
+    }
+}
+
+struct Aligner<'language> {
+    from: &'language Language,
+    to: &'language Language,
+    from_config: AnalysisConfig<'language>,
+    to_config: AnalysisConfig<'language>
+}
+
+impl<'language> Aligner<'language> {
+    fn new(from: &'language Language, to: &'language Language) -> Self {
+        Self { from,
+               to,
+               from_config: AnalysisConfig::from_language(from),
+               to_config: AnalysisConfig::from_language(to) }
+    }
+
+    fn substitution_cost(&self, source: &Rc<Phoneme>, target: &Rc<Phoneme>) -> usize {
+        if source.name == target.name {
+            return 0;
+        }
+        let source_sets = self.from.inventory().sets_containing(source);
+        let target_sets = self.to.inventory().sets_containing(target);
+        let shared = source_sets.iter().filter(|set| target_sets.contains(set)).count();
+        let different = source_sets.len() + target_sets.len() - (shared * 2);
+        let cost = (MAX_SUBSTITUTION_COST * different).div_ceil((shared + different).max(1));
+        // languages without complete cluster sets just don't get the penalty.
+        match (self.from_config.find_cluster_set_for_phoneme(source), self.to_config.find_cluster_set_for_phoneme(target)) {
+            (Ok(source_set), Ok(target_set)) if source_set != target_set => cost + CLUSTER_PENALTY,
+            _ => cost
+        }
+    }
+
+    // This is the usual edit distance table, with the cost of the best alignment of the first i source phonemes and first j target phonemes at i * width + j.
+    fn align(&self, source: &Word, target: &Word) -> Vec<(Segment, Segment)> {
+        let source = source.phonemes();
+        let target = target.phonemes();
+        let width = target.len() + 1;
+        let mut costs = vec![0; (source.len() + 1) * width];
+        let at = |table: &[usize], i: usize, j: usize| table.get(i * width + j).copied().unwrap_or(usize::MAX);
+
+        let substitution = |i: usize, j: usize| match (source.get(i), target.get(j)) {
+            (Some(source), Some(target)) => self.substitution_cost(source, target),
+            _ => usize::MAX
+        };
+
+        for i in 0..=source.len() {
+            for j in 0..=target.len() {
+                let cost = match (i.checked_sub(1), j.checked_sub(1)) {
+                    (None, _) => j * GAP_COST,
+                    (_, None) => i * GAP_COST,
+                    (Some(previous_i), Some(previous_j)) => {
+                        let matched = at(&costs, previous_i, previous_j).saturating_add(substitution(previous_i, previous_j));
+                        let lost = at(&costs, previous_i, j).saturating_add(GAP_COST);
+                        let inserted = at(&costs, i, previous_j).saturating_add(GAP_COST);
+                        matched.min(lost).min(inserted)
+                    }
+                };
+                if let Some(cell) = costs.get_mut(i * width + j) {
+                    *cell = cost;
+                }
+            }
+        }
+
+        // walk back from the end, preferring matches over gaps when the cost is the same.
+        let mut result = Vec::new();
+        let mut i = source.len();
+        let mut j = target.len();
+        while i > 0 || j > 0 {
+            let cost = at(&costs, i, j);
+            let source_phoneme = i.checked_sub(1).and_then(|previous| source.get(previous));
+            let target_phoneme = j.checked_sub(1).and_then(|previous| target.get(previous));
+            if let (Some(source_phoneme), Some(target_phoneme)) = (source_phoneme, target_phoneme)
+               && at(&costs, i - 1, j - 1).saturating_add(substitution(i - 1, j - 1)) == cost
+            {
+                result.push((Segment(Some(source_phoneme.name)), Segment(Some(target_phoneme.name))));
+                i -= 1;
+                j -= 1;
+            } else if let Some(source_phoneme) = source_phoneme
+                      && (j == 0 || at(&costs, i - 1, j).saturating_add(GAP_COST) == cost)
+            {
+                result.push((Segment(Some(source_phoneme.name)), Segment(None)));
+                i -= 1;
+            } else if let Some(target_phoneme) = target_phoneme {
+                result.push((Segment(None), Segment(Some(target_phoneme.name))));
+                j -= 1;
+            } else {
+                break;
+            }
+        }
+        result.reverse();
+        result
+    }
+}
+
+/// Reads pairs of cognates from a CSV file, which has a column named for each language. Other columns are ignored. If both languages are the same, the first two columns with its name are used.
+pub(crate) fn read_cognate_pairs<P: AsRef<Path>>(path: P, from: &str, to: &str) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let mut reader = Reader::from_path(path)?;
+    let headers = reader.headers()?.clone();
+    let find_column = |name: &str, skip: Option<usize>| {
+        headers.iter().enumerate().position(|(i, header)| Some(i) != skip && header.to_lowercase() == name.to_lowercase()).ok_or_else(|| format!("No '{name}' field found."))
+    };
+    let from_column = find_column(from, None)?;
+    let to_column = find_column(to, Some(from_column))?;
+
+    let mut result = Vec::new();
+    for (row, record) in reader.into_records().enumerate() {
+        let record = record.map_err(|e| format!("Error reading record {row}: {e}"))?;
+        let source = record.get(from_column).ok_or_else(|| format!("Missing {from} field in {row}"))?;
+        let target = record.get(to_column).ok_or_else(|| format!("Missing {to} field in {row}"))?;
+        result.push((source.trim_matches('/').to_owned(), target.trim_matches('/').to_owned()));
+    }
+    Ok(result)
+}
+
+/// Aligns each pair of cognates. See the note at the top of this file.
+pub(crate) fn align_cognates(from: &Language, to: &Language, pairs: &[(Word, Word)]) -> Vec<Cognates> {
+    let aligner = Aligner::new(from, to);
+    pairs.iter()
+         .map(|(source, target)| Cognates { source: source.clone(),
+                                            target: target.clone(),
+                                            alignment: aligner.align(source, target) })
+         .collect()
+}
+
+/// Counts the correspondences in the aligned cognates, with the index of each pair of cognates they were found in. They're sorted by the source phoneme.
+pub(crate) fn count_correspondences(cognates: &[Cognates]) -> BTreeMap<(Segment, Segment), Vec<usize>> {
+    let mut result: BTreeMap<(Segment, Segment), Vec<usize>> = BTreeMap::new();
+    for (i, pair) in cognates.iter().enumerate() {
+        for correspondence in &pair.alignment {
+            let found = result.entry(correspondence.clone()).or_default();
+            if !found.contains(&i) {
+                found.push(i);
+            }
+        }
+    }
+    result
+}
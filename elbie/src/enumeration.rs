@@ -0,0 +1,192 @@
+use crate::errors::ElbieError;
+use crate::language::Language;
+use crate::phoneme::Phoneme;
+use crate::phonotactics::AddPhoneme;
+use crate::phonotactics::Choice;
+use crate::phonotactics::NamedOrInlineBranches;
+use crate::phonotactics::Optional;
+use crate::phonotactics::Pattern;
+use crate::phonotactics::PatternSet;
+use crate::phonotactics::RuleReference;
+use crate::phonotactics::Sequence;
+use crate::phonotactics::Series;
+use crate::phonotactics::TerminateWord;
+use crate::phonotactics::Tree;
+use crate::phonotactics::TreeBranches;
+use crate::word::Word;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/* NOTE:
+
+Enumeration works like generation, except that instead of choosing one path through each pattern, every path is followed. Each pattern takes a list of partial words and returns every way those words could be extended by the pattern. Any partial word that would go over the maximum length is dropped, which is what keeps series and recursive rules from running forever.
+
+Because the validator is greedy (series and options match as much as they can, choices take the first branch that matches), it's possible to enumerate a word that the validator would reject. So the results of this should always be run through validation before they're trusted.
+
+*/
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct PartialWord {
+    word: Word,
+    is_complete: bool
+}
+
+fn dedup(mut partials: Vec<PartialWord>) -> Vec<PartialWord> {
+    let mut seen = HashSet::new();
+    partials.retain(|partial| seen.insert(partial.clone()));
+    partials
+}
+
+trait EnumerateWords {
+    fn enumerate_words(&self, language: &Language, max_length: usize, partials: &[PartialWord]) -> Result<Vec<PartialWord>, ElbieError>;
+}
+
+impl EnumerateWords for Sequence {
+    fn enumerate_words(&self, language: &Language, max_length: usize, partials: &[PartialWord]) -> Result<Vec<PartialWord>, ElbieError> {
+        let mut result = partials.to_vec();
+        for pattern in &self.patterns {
+            result = pattern.enumerate_words(language, max_length, &result)?;
+        }
+        Ok(result)
+    }
+}
+
+impl EnumerateWords for Series {
+    fn enumerate_words(&self, language: &Language, max_length: usize, partials: &[PartialWord]) -> Result<Vec<PartialWord>, ElbieError> {
+        let mut result = Vec::new();
+        let mut current = partials.to_vec();
+        let mut count = 0;
+        loop {
+            if count >= self.minimum {
+                result.extend(current.iter().cloned());
+            }
+            // The last check is in case the pattern can match nothing, in which case the words would never get longer.
+            if current.is_empty() || self.maximum.is_some_and(|max| count >= max) || count > (max_length + self.minimum) {
+                break;
+            }
+            current = dedup(self.pattern.enumerate_words(language, max_length, &current)?);
+            count += 1;
+        }
+        Ok(dedup(result))
+    }
+}
+
+impl EnumerateWords for Optional {
+    fn enumerate_words(&self, language: &Language, max_length: usize, partials: &[PartialWord]) -> Result<Vec<PartialWord>, ElbieError> {
+        let mut result = partials.to_vec();
+        result.extend(self.pattern.enumerate_words(language, max_length, partials)?);
+        Ok(dedup(result))
+    }
+}
+
+impl EnumerateWords for Choice {
+    fn enumerate_words(&self, language: &Language, max_length: usize, partials: &[PartialWord]) -> Result<Vec<PartialWord>, ElbieError> {
+        let mut result = Vec::new();
+        for (branch, _weight) in self.branches.items() {
+            result.extend(branch.body.enumerate_words(language, max_length, partials)?);
+        }
+        Ok(dedup(result))
+    }
+}
+
+#[allow(clippy::multiple_inherent_impl, reason = "I want to separate validation, generation and enumeration from the patterns")]
+impl AddPhoneme {
+    fn enumerate_with_phoneme(&self, language: &Language, max_length: usize, partials: &[PartialWord]) -> Result<Vec<(PartialWord, Rc<Phoneme>)>, ElbieError> {
+        let set = language.inventory().get_set(self.name)?;
+        let mut result = Vec::new();
+        for partial in partials {
+            if partial.is_complete || partial.word.phonemes().len() >= max_length {
+                continue;
+            }
+            for phoneme in set.items() {
+                if self.avoid_duplicates && partial.word.last() == Some(phoneme) {
+                    continue;
+                }
+                let mut extended = partial.clone();
+                extended.word.push(phoneme.clone());
+                result.push((extended, phoneme.clone()));
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl EnumerateWords for AddPhoneme {
+    fn enumerate_words(&self, language: &Language, max_length: usize, partials: &[PartialWord]) -> Result<Vec<PartialWord>, ElbieError> {
+        Ok(self.enumerate_with_phoneme(language, max_length, partials)?.into_iter().map(|(partial, _)| partial).collect())
+    }
+}
+
+#[allow(clippy::multiple_inherent_impl, reason = "I want to separate validation, generation and enumeration from the patterns")]
+impl TreeBranches {
+    // not an EnumerateWords trait because it requires the phoneme information that was just added.
+    fn enumerate_words(&self, phoneme: &Rc<Phoneme>, language: &Language, max_length: usize, partial: PartialWord) -> Result<Vec<PartialWord>, ElbieError> {
+        for branch in &self.branches {
+            if language.inventory().phoneme_is(phoneme, branch.condition_set)? {
+                return branch.body.enumerate_words(language, max_length, &[partial]);
+            }
+        }
+        // Generation would fail with an error here, but validation just rejects the word, so there's nothing to enumerate.
+        Ok(Vec::new())
+    }
+}
+
+impl EnumerateWords for Tree {
+    fn enumerate_words(&self, language: &Language, max_length: usize, partials: &[PartialWord]) -> Result<Vec<PartialWord>, ElbieError> {
+        let environment = match &self.environment {
+            NamedOrInlineBranches::Inline(environment) => environment,
+            NamedOrInlineBranches::Named(name) => language.patterns().get_named_branches(name)?
+        };
+        let mut result = Vec::new();
+        for (partial, phoneme) in self.initial.enumerate_with_phoneme(language, max_length, partials)? {
+            result.extend(environment.enumerate_words(&phoneme, language, max_length, partial)?);
+        }
+        Ok(dedup(result))
+    }
+}
+
+impl EnumerateWords for TerminateWord {
+    fn enumerate_words(&self, _: &Language, _: usize, partials: &[PartialWord]) -> Result<Vec<PartialWord>, ElbieError> {
+        Ok(partials.iter()
+                   .map(|partial| PartialWord { word: partial.word.clone(),
+                                                is_complete: true })
+                   .collect())
+    }
+}
+
+impl EnumerateWords for RuleReference {
+    fn enumerate_words(&self, language: &Language, max_length: usize, partials: &[PartialWord]) -> Result<Vec<PartialWord>, ElbieError> {
+        let pattern = language.patterns().get(self.name)?;
+        pattern.enumerate_words(language, max_length, partials)
+    }
+}
+
+impl EnumerateWords for Pattern {
+    fn enumerate_words(&self, language: &Language, max_length: usize, partials: &[PartialWord]) -> Result<Vec<PartialWord>, ElbieError> {
+        // Without this, recursive rules would never stop.
+        if partials.is_empty() {
+            return Ok(Vec::new());
+        }
+        match self {
+            Self::Sequence(sequence) => sequence.enumerate_words(language, max_length, partials),
+            Self::Series(series) => series.enumerate_words(language, max_length, partials),
+            Self::Option(optional) => optional.enumerate_words(language, max_length, partials),
+            Self::Choice(choice) => choice.enumerate_words(language, max_length, partials),
+            Self::Tree(switch) => switch.enumerate_words(language, max_length, partials),
+            Self::RuleReference(reference) => reference.enumerate_words(language, max_length, partials),
+            Self::Set(set) => set.enumerate_words(language, max_length, partials),
+            Self::Terminate(terminate) => terminate.enumerate_words(language, max_length, partials)
+        }
+    }
+}
+
+#[allow(clippy::multiple_inherent_impl, reason = "I want to separate validation, generation and enumeration from the patterns")]
+impl PatternSet {
+    /// Returns every word the patterns could produce which has no more than `max_length` phonemes. The words have not been validated.
+    pub(crate) fn enumerate(&self, language: &Language, max_length: usize) -> Result<Vec<Word>, ElbieError> {
+        let start = PartialWord { word: Word::new(&[]),
+                                  is_complete: false };
+        let partials = self.initial.enumerate_words(language, max_length, &[start])?;
+        Ok(partials.into_iter().map(|partial| partial.word).filter(|word| !word.phonemes().is_empty()).collect())
+    }
+}
@@ -36,6 +36,8 @@ pub enum ElbieError {
     IncompleteBranches(&'static str),
     #[error("Phoneme '{0}' was added with {2} spellings, but {1} were expected.")]
     MismatchedSpellingsForPhoneme(&'static str, usize, usize),
+    #[error("The language already has a respelling orthography.")]
+    RespellingOrthographyAlreadyAdded,
 
     // word generation errors
     #[error("Phoneme added after termination pattern.")]
@@ -74,10 +76,28 @@ pub enum ElbieError {
     LexiconParsingError(usize, Box<Self>),
     #[error("No definition found at row {0}")]
     NoDefinitionFoundAt(usize),
+    #[error("At least one primary orthography is required for a lexicon.")]
+    NoPrimaryOrthography,
+
+    // text errors
+    #[error("Error reading text at line {0}: {1}")]
+    TextParsingError(usize, Box<Self>),
 
     // transformation errors
     #[error("Transformation rule '{0}' created an overlapping splice.")]
     TransformationCreatedOverlappingReplacements(&'static str),
+    #[error("Transformation rule '{0}' was still changing the word after {1} applications.")]
+    TransformationDidNotFinish(&'static str, usize),
+    #[error("Unknown transformation rule '{0}'.")]
+    UnknownTransformationRule(&'static str),
+    #[error("Transformation rule '{0}' has a probability of {1}, which is not between 0 and 1.")]
+    InvalidRuleProbability(&'static str, f64),
+    #[error("Could not parse sound change '{0}': {1}")]
+    InvalidSoundChange(String, String),
+    #[error("There isn't exactly one phoneme like /{0}/ with [{1}] changed to [{2}].")]
+    NoPhonemeWithChangedFeatures(&'static str, String, String),
+    #[error("Capture key '{0}' is also the name of a phoneme.")]
+    CaptureKeyIsPhoneme(&'static str),
 
     // family errors
     #[error("Language '{0}' was already added.")]
@@ -92,8 +112,16 @@ pub enum ElbieError {
     UnknownTransformation(String, String),
     #[error("Transformation for '{0}' => '{1}' needs to be loaded before it can be accessed.")]
     TransformationNotLoaded(String, String),
+    #[error("Transformation '{0}' => '{1}' is part of a chain, so it must be a single transformation, not a set.")]
+    ChainStepNotSingle(String, String),
     #[error("No default language available.")]
     NoDefaultLanguage,
+    #[error("Language '{0}' has no allophonic rules.")]
+    NoAllophony(&'static str),
+    #[error("Timeline '{0}' was already added.")]
+    TimelineAlreadyAdded(String),
+    #[error("Unknown timeline '{0}'")]
+    UnknownTimeline(String),
 
     // analysis errores
     #[error("Phoneme /{0}/ is found in both {1} and {2}.")]
@@ -105,7 +133,9 @@ pub enum ElbieError {
     #[error("Only simple transformations can be made with this function call.")]
     TransformationSetsNotAllowedHere,
     #[error("Orthography index {0} is not available.")]
-    UnknownOrthography(usize)
+    UnknownOrthography(usize),
+    #[error("Orthography '{0}' is not available.")]
+    UnknownOrthographyName(String)
 }
 
 #[deprecated(since = "0.2.2", note = "Use `ElbieError` instead.")]
@@ -1,18 +1,29 @@
+use crate::adaptation;
 use crate::errors::ElbieError;
 use crate::language::Language;
+use crate::text;
 use crate::transformation::PreparedTransformation;
+use crate::transformation::Timeline;
+use crate::transformation::TimelineStage;
 use crate::transformation::Transformation;
+use crate::transformation::TransformationChain;
 use crate::transformation::TransformationEntry;
+use crate::transformation::TransformationResult;
 use crate::transformation::TransformationSet;
+use crate::transformation::TransformationTraceCallback;
+use crate::validation::ValidationTraceCallback;
 use crate::word::Word;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 
 type LanguageCreator = Box<dyn FnOnce() -> Result<Language, ElbieError>>;
 type TransformationCreator = Box<dyn FnOnce(&mut Family) -> Result<Transformation, ElbieError>>;
 
 enum TransformationEntryCreator {
     Single(TransformationCreator),
-    Set(TransformationSet)
+    Set(TransformationSet),
+    Chain(TransformationChain)
 }
 
 #[derive(Default)]
@@ -21,7 +32,8 @@ pub struct Family {
     delayed_languages: HashMap<String, LanguageCreator>,
     languages: HashMap<String, Language>,
     delayed_transformations: HashMap<(String, String), TransformationEntryCreator>,
-    transformations: HashMap<(String, String), TransformationEntry>
+    transformations: HashMap<(String, String), TransformationEntry>,
+    timelines: HashMap<String, Timeline>
 }
 
 impl Family {
@@ -64,6 +76,31 @@ impl Family {
         }
     }
 
+    /// Adds a transformation which applies each step to the result of the step before. Each step is a source language and the name of a transformation from that language, which must not be a set. For example, `family.transformation_chain(GOBLIN, HOBGOBLIN, &[(GOBLIN, MIDDLE_GOBLIN), (MIDDLE_GOBLIN, HOBGOBLIN)])`. The `--chain` option of the transform commands can find a chain between two languages by following the validation languages of the transformations, so this is only needed to pick a specific path, or to give the chain a name.
+    pub fn transformation_chain(&mut self, from: &'static str, name: &'static str, steps: &[(&'static str, &'static str)]) -> Result<(), ElbieError> {
+        let steps = steps.iter().map(|(step_from, step_name)| ((*step_from).to_owned(), (*step_name).to_owned())).collect();
+        match self.delayed_transformations.insert((from.to_owned(), name.to_owned()), TransformationEntryCreator::Chain(TransformationChain::new(steps))) {
+            Some(_) => Err(ElbieError::TransformationAlreadyAdded(from.to_owned(), name.to_owned())),
+            None => Ok(())
+        }
+    }
+
+    /// Adds a timeline of sound changes. Each stage is the date the changes happened, and the source language and name of a transformation from that language. The transformations will usually be from the language the previous stage validated as. Words can be given a date they entered the language, and only the stages after that date are applied to them, which is how loanwords and late coinages should be handled.
+    pub fn timeline(&mut self, name: &'static str, stages: &[(i64, &'static str, &'static str)]) -> Result<(), ElbieError> {
+        match self.timelines.insert(name.to_owned(), Timeline::new(stages)) {
+            Some(_) => Err(ElbieError::TimelineAlreadyAdded(name.to_owned())),
+            None => Ok(())
+        }
+    }
+
+    pub(crate) fn timeline_stages(&self, name: &str) -> Result<&[(i64, String, String)], ElbieError> {
+        self.timelines.get(name).map(Timeline::stages).ok_or_else(|| ElbieError::UnknownTimeline(name.to_owned()))
+    }
+
+    pub(crate) fn timeline_keys(&self) -> Vec<String> {
+        self.timelines.keys().cloned().collect()
+    }
+
     pub(crate) fn language_keys(&self) -> Vec<String> {
         self.delayed_languages.keys().chain(self.languages.keys()).cloned().collect()
     }
@@ -77,15 +114,108 @@ impl Family {
 
         match self.delayed_transformations.get(key) {
             Some(TransformationEntryCreator::Set(items)) => Ok(Some(items.items())),
-            Some(TransformationEntryCreator::Single(_)) => Ok(None),
+            Some(TransformationEntryCreator::Single(_) | TransformationEntryCreator::Chain(_)) => Ok(None),
             None => match self.transformations.get(key) {
                 Some(TransformationEntry::Set(items)) => Ok(Some(items.items())),
-                Some(TransformationEntry::Single(_)) => Ok(None),
+                Some(TransformationEntry::Single(_) | TransformationEntry::Chain(_)) => Ok(None),
                 None => Err(ElbieError::UnknownTransformation(from.to_owned(), name.to_owned()))
             }
         }
     }
 
+    pub(crate) fn transformation_chain_contents(&self, from: &str, name: &str) -> Result<Option<&[(String, String)]>, ElbieError> {
+        let key = &(from.to_owned(), name.to_owned());
+
+        match self.delayed_transformations.get(key) {
+            Some(TransformationEntryCreator::Chain(chain)) => Ok(Some(chain.steps())),
+            Some(TransformationEntryCreator::Single(_) | TransformationEntryCreator::Set(_)) => Ok(None),
+            None => match self.transformations.get(key) {
+                Some(TransformationEntry::Chain(chain)) => Ok(Some(chain.steps())),
+                Some(TransformationEntry::Single(_) | TransformationEntry::Set(_)) => Ok(None),
+                None => Err(ElbieError::UnknownTransformation(from.to_owned(), name.to_owned()))
+            }
+        }
+    }
+
+    /// Searches for the shortest chain of single transformations from one language to another, following the validation language of each one to the transformations from that language, and returns the source and name of each step. This has to load every transformation it looks at. The chain isn't added to the family, see `get_chain_transformations`. Returns an UnknownTransformation error if there is no chain.
+    pub(crate) fn find_transformation_chain(&mut self, from: &str, to: &str) -> Result<Vec<(String, String)>, ElbieError> {
+        let mut reached_by: HashMap<String, (String, String)> = HashMap::new();
+        let mut visited = HashSet::from([from.to_owned()]);
+        let mut queue = VecDeque::from([from.to_owned()]);
+
+        while let Some(language) = queue.pop_front() {
+            let mut keys: Vec<(String, String)> = self.transformation_keys().into_iter().filter(|(source, _)| *source == language).collect();
+            // sorted so the same path is found every time.
+            keys.sort();
+            for key in keys {
+                // sets and chains are made up of single transformations anyway.
+                if self.transformation_set_contents(&key.0, &key.1)?.is_some() || self.transformation_chain_contents(&key.0, &key.1)?.is_some() {
+                    continue;
+                }
+                self.load_transformation(&key.0, &key.1)?;
+                let target = match self.get_transformation(&key.0, &key.1)? {
+                    TransformationEntry::Single(transformation) => transformation.validation_language().map(ToOwned::to_owned),
+                    TransformationEntry::Set(_) | TransformationEntry::Chain(_) => None
+                };
+                if let Some(target) = target
+                   && visited.insert(target.clone())
+                {
+                    _ = reached_by.insert(target.clone(), key);
+                    queue.push_back(target);
+                }
+            }
+            if reached_by.contains_key(to) {
+                break;
+            }
+        }
+
+        let mut steps = Vec::new();
+        let mut current = to.to_owned();
+        while let Some(step) = reached_by.remove(&current) {
+            current.clone_from(&step.0);
+            steps.push(step);
+        }
+        steps.reverse();
+        if steps.is_empty() {
+            Err(ElbieError::UnknownTransformation(from.to_owned(), to.to_owned()))
+        } else {
+            Ok(steps)
+        }
+    }
+
+    /// Loads every single transformation which can be reached from the source language, following the validation language of each one to the transformations from that language. Returns the source and name of each in the order they were found, with the index of the key whose results it transforms, or None if it transforms words in the source language.
+    /// Each language is only visited once, so if more than one transformation validates as the same language, only the results of the first one found are transformed further. The others are still returned, but nothing descends from them.
+    pub(crate) fn load_descendants(&mut self, source: &str) -> Result<Vec<(String, String, Option<usize>)>, ElbieError> {
+        let mut result = Vec::new();
+        let mut visited = HashSet::from([source.to_owned()]);
+        let mut queue = VecDeque::from([(source.to_owned(), None)]);
+
+        while let Some((language, parent)) = queue.pop_front() {
+            let mut keys: Vec<(String, String)> = self.transformation_keys().into_iter().filter(|(from, _)| *from == language).collect();
+            keys.sort();
+            for key in keys {
+                // sets and chains are made up of single transformations, which will be found anyway.
+                if self.transformation_set_contents(&key.0, &key.1)?.is_some() || self.transformation_chain_contents(&key.0, &key.1)?.is_some() {
+                    continue;
+                }
+                self.load_transformation(&key.0, &key.1)?;
+                let target = match self.get_transformation(&key.0, &key.1)? {
+                    TransformationEntry::Single(transformation) => transformation.validation_language().map(ToOwned::to_owned),
+                    TransformationEntry::Set(_) | TransformationEntry::Chain(_) => None
+                };
+                if let Some(target) = target
+                   && visited.insert(target.clone())
+                {
+                    self.load_language(&target)?;
+                    queue.push_back((target, Some(result.len())));
+                }
+                result.push((key.0, key.1, parent));
+            }
+        }
+
+        Ok(result)
+    }
+
     // I originally tried to do this automatically in the get_language, but because of the mutable borrow, I could only get and keep one language
     // at a time, which became a problem with transformations, which need two languages. There may be better ways to solve this, but for now
     // it's pretty clear if the programmer fails to load, because they'll get a NotLoaded error.
@@ -125,6 +255,12 @@ impl Family {
                         self.load_transformation(from, item)?;
                     }
                     _ = self.transformations.insert(key.clone(), TransformationEntry::Set(transformation_set));
+                },
+                TransformationEntryCreator::Chain(chain) => {
+                    for (step_from, step_name) in chain.steps() {
+                        self.load_transformation(step_from, step_name)?;
+                    }
+                    _ = self.transformations.insert(key.clone(), TransformationEntry::Chain(chain));
                 }
             }
 
@@ -186,6 +322,19 @@ impl Family {
                     self.extend_transformations(from, transformation, load_validators, transformations)?;
                 }
             },
+            TransformationEntry::Chain(chain) => self.extend_chain(chain.steps(), load_validators, transformations)?
+        }
+        Ok(())
+    }
+
+    fn extend_chain<'me>(&'me self, steps: &[(String, String)], load_validators: bool, transformations: &mut Vec<PreparedTransformation<'me, 'me>>) -> Result<(), ElbieError> {
+        for (index, (step_from, step_name)) in steps.iter().enumerate() {
+            let start = transformations.len();
+            self.extend_transformations(step_from, step_name, load_validators, transformations)?;
+            match transformations.get_mut(start..) {
+                Some([step]) => step.chained = index > 0,
+                _ => return Err(ElbieError::ChainStepNotSingle(step_from.clone(), step_name.clone()))
+            }
         }
         Ok(())
     }
@@ -196,11 +345,82 @@ impl Family {
         Ok(result)
     }
 
+    /// Prepares the steps of a chain which isn't in the family, such as one from `find_transformation_chain`, like `get_transformations` does for a chain added with `transformation_chain`.
+    pub(crate) fn get_chain_transformations(&self, steps: &[(String, String)], load_validators: bool) -> Result<Vec<PreparedTransformation<'_, '_>>, ElbieError> {
+        let mut result = Vec::new();
+        self.extend_chain(steps, load_validators, &mut result)?;
+        Ok(result)
+    }
+
+    pub(crate) fn load_timeline(&mut self, name: &str) -> Result<(), ElbieError> {
+        let stages = self.timeline_stages(name)?.to_vec();
+        for (_, from, transformation) in stages {
+            self.load_language(&from)?;
+            self.load_transformation(&from, &transformation)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn get_timeline(&self, name: &str, load_validators: bool) -> Result<Vec<TimelineStage<'_>>, ElbieError> {
+        let mut result = Vec::new();
+        for (date, from, transformation) in self.timeline_stages(name)? {
+            let transformations = self.get_transformations(from, transformation, load_validators)?;
+            // a set would give more than one result for a stage, and the next stage wouldn't know which one to use.
+            if transformations.iter().skip(1).any(|item| !item.chained) {
+                return Err(ElbieError::TransformationSetsNotAllowedHere);
+            }
+            result.push(TimelineStage { date: *date,
+                                        language: self.get_language(from)?,
+                                        transformations });
+        }
+        Ok(result)
+    }
+
     // I need some tools to do this stuff programattically
+    /// Transforms a word with a transformation, which may be a chain, and returns the final word, and whether it was valid if `validate` is true. Only one word is returned, so if a rule is optional or has alternatives, this is the first variant, where every optional rule applied. Use `transform_word_with_set` to get every variant.
     pub fn transform_word(&mut self, word: &str, source: &str, target: &str, validate: bool) -> Result<(Word, Option<bool>), ElbieError> {
         self.load_transformation(source, target)?;
         let source = self.get_language(source)?;
         let transformations = self.get_transformations(source.name(), target, validate)?;
+
+        // override the value of replace_word, so we don't ever do that again
+        let mut transformed = source.read_word(word)?;
+        let mut validated = None;
+
+        // a chain is allowed, since it still produces one word.
+        for (index, transformer) in transformations.iter().enumerate() {
+            if index > 0 && !transformer.chained {
+                return Err(ElbieError::TransformationSetsNotAllowedHere);
+            }
+
+            // if there are variants, the first is the one where every optional rule applied.
+            transformed = transformer.transformation.transform(&transformed, &HashMap::new(), None)?.into_iter().next().unwrap_or(transformed);
+
+            if let Some(validator) = transformer.validator {
+                let valid = validator.check_word(&transformed, None)?.is_ok();
+                validated = Some(validated.unwrap_or(true) && valid);
+            }
+        }
+
+        Ok((transformed, validated))
+    }
+
+    /// Transforms a word with a transformation, which may be a set or a chain, returning a result for each transformation in it. Unlike `transform_word`, every variant of the result is returned. If `validate` is false, the results aren't validated.
+    pub fn transform_word_with_set(&mut self, word: &str, source: &str, target: &str, validate: bool) -> Result<Vec<TransformationResult>, ElbieError> {
+        self.load_transformation(source, target)?;
+        let source = self.get_language(source)?;
+        let transformations = self.get_transformations(source.name(), target, validate)?;
+
+        let word = source.read_word(word)?;
+
+        transform_with_set(&word, &HashMap::new(), &transformations, false, None, None)
+    }
+
+    /// Finds words in the source language, up to `max_length` phonemes, which the transformation turns into the specified word. The length should allow for any phonemes the rules delete, and the search gets slow very quickly as it grows. See `Transformation::add_rule_examples` for how the word is written.
+    pub fn find_ancestors(&mut self, word: &str, source: &str, target: &str, max_length: usize) -> Result<Vec<Word>, ElbieError> {
+        self.load_transformation(source, target)?;
+        let source = self.get_language(source)?;
+        let transformations = self.get_transformations(source.name(), target, false)?;
         let transformer = if transformations.len() == 1
                              && let Some(transformation) = transformations.first()
         {
@@ -209,17 +429,8 @@ impl Family {
             return Err(ElbieError::TransformationSetsNotAllowedHere);
         };
 
-        // override the value of replace_word, so we don't ever do that again
-        let word = source.read_word(word)?;
-        let transformed = transformer.transformation.transform(&word, None)?;
-
-        let validated = if let Some(validator) = transformer.validator {
-            Some(validator.check_word(&transformed, None)?.is_ok())
-        } else {
-            None
-        };
-
-        Ok((transformed, validated))
+        let word = transformer.transformation.read_word(word)?;
+        Ok(transformer.transformation.find_sources(source, &[word], max_length)?.into_iter().flatten().collect())
     }
 
     pub fn validate_word(&mut self, word: &str, source: &str) -> Result<(Word, bool), ElbieError> {
@@ -234,6 +445,17 @@ impl Family {
         Ok((word, validated))
     }
 
+    /// Adapts a loanword from one language into another, by finding the closest phonemes in the target language and then repairing the result so it's valid. Returns every adaptation with the lowest cost, which is empty if none could be found. See the note in `adaptation.rs` for details.
+    pub fn adapt_word(&mut self, word: &str, from: &str, to: &str) -> Result<Vec<Word>, ElbieError> {
+        self.load_language(from)?;
+        self.load_language(to)?;
+        let source = self.get_language(from)?;
+
+        let word = source.read_word(word)?;
+
+        Ok(adaptation::adapt_word(source, self.get_language(to)?, &word)?.adapted)
+    }
+
     pub fn spell_word(&mut self, word: &str, source: &str, orthography: usize) -> Result<String, ElbieError> {
         self.load_language(source)?;
         let language = self.get_language(source)?;
@@ -243,8 +465,89 @@ impl Family {
         }
 
         let word = language.read_word(word)?;
-        let word = language.spell_word(&word, orthography);
+        let word = language.spell_word(&word, orthography)?;
 
         Ok(word)
     }
+
+    pub fn spell_word_by_name(&mut self, word: &str, source: &str, orthography: &str) -> Result<String, ElbieError> {
+        self.load_language(source)?;
+        let orthography = self.get_language(source)?.find_orthography(orthography)?;
+
+        self.spell_word(word, source, orthography)
+    }
+
+    pub fn spell_text(&mut self, text: &str, source: &str, orthography: usize) -> Result<String, ElbieError> {
+        self.load_language(source)?;
+        let language = self.get_language(source)?;
+
+        text::spell_text(language, text, orthography)
+    }
+
+    pub fn spell_text_by_name(&mut self, text: &str, source: &str, orthography: &str) -> Result<String, ElbieError> {
+        self.load_language(source)?;
+        let orthography = self.get_language(source)?.find_orthography(orthography)?;
+
+        self.spell_text(text, source, orthography)
+    }
+}
+
+/// Validates the word, and if `explain` is true, prints how each part of a valid word matched the language's patterns.
+pub(crate) fn validate_word(language: &Language, word: &Word, explain: bool, trace_cb: Option<&ValidationTraceCallback>) -> Result<Result<(), ()>, ElbieError> {
+    match language.check_word(word, trace_cb)? {
+        Err(()) => Ok(Err(())),
+        Ok(validated) => {
+            if explain {
+                eprintln!("Explain: {word}");
+                for valid in validated {
+                    eprintln!("{valid}")
+                }
+            }
+
+            Ok(Ok(()))
+        }
+    }
+}
+
+/// Transforms each of the words, which might be the variants from a previous transformation in a chain, and validates all of the results.
+pub(crate) fn transform_and_validate_word(words: &[Word], attributes: &HashMap<String, String>, transformation: &Transformation, validator: Option<&Language>, explain: bool,
+                                          transformation_trace_cb: Option<&TransformationTraceCallback>, validation_trace_cb: Option<&ValidationTraceCallback>)
+                                          -> Result<(Vec<Word>, Option<bool>), ElbieError> {
+    let mut transformed = Vec::new();
+    for word in words {
+        for variant in transformation.transform(word, attributes, transformation_trace_cb)? {
+            if !transformed.contains(&variant) {
+                transformed.push(variant);
+            }
+        }
+    }
+
+    if let Some(validator) = validator {
+        // every variant is validated, even after one fails, so the trace and explanation cover all of them.
+        let mut valid = true;
+        for variant in &transformed {
+            valid = validate_word(validator, variant, explain, validation_trace_cb)?.is_ok() && valid;
+        }
+        Ok((transformed, Some(valid)))
+    } else {
+        Ok((transformed, None))
+    }
+}
+
+/// Transforms a word with every transformation in a list, returning a result for each one. Chained transformations are applied to the result of the previous one.
+pub(crate) fn transform_with_set(word: &Word, attributes: &HashMap<String, String>, transformations: &[PreparedTransformation], explain: bool,
+                                 transformation_trace_cb: Option<&TransformationTraceCallback>, validation_trace_cb: Option<&ValidationTraceCallback>)
+                                 -> Result<Vec<TransformationResult>, ElbieError> {
+    let mut results: Vec<TransformationResult> = Vec::new();
+    for item in transformations {
+        let inputs = match results.last() {
+            Some(previous) if item.chained => previous.words.clone(),
+            _ => vec![word.clone()]
+        };
+        let (words, valid) = transform_and_validate_word(&inputs, attributes, item.transformation, item.validator, explain, transformation_trace_cb, validation_trace_cb)?;
+        results.push(TransformationResult { name: item.name.clone(),
+                                            words,
+                                            valid });
+    }
+    Ok(results)
 }
@@ -0,0 +1,281 @@
+use crate::errors::ElbieError;
+use crate::family::Family;
+use crate::transformation::TransformationEntry;
+use core::fmt::Write as _;
+use core::str::FromStr;
+use html_builder::Html5 as _;
+use std::io;
+use std::io::Write;
+
+/*
+NOTE: The family tree shows languages, the transformations from them, and the languages those transformations validate as, which is how one language descends from another. Sets and chains point to the transformations they're made of, with dashed lines in the graphs.
+
+The graph formats show everything as it is. The Markdown and HTML formats are nested lists, starting with the languages that don't descend from any others. A language which has already been listed is marked "see above" instead of being listed again, so cycles don't go on forever.
+*/
+
+pub(crate) enum FamilyTreeFormat {
+    DOT,
+    Mermaid,
+    Markdown,
+    HTML
+}
+
+impl FromStr for FamilyTreeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "dot" => Ok(Self::DOT),
+            "mermaid" => Ok(Self::Mermaid),
+            "markdown" => Ok(Self::Markdown),
+            "html" => Ok(Self::HTML),
+            name => Err(format!("Unknown family tree format '{name}'."))
+        }
+    }
+}
+
+enum Branch {
+    // the validation language
+    Single(Option<String>),
+    Set(Vec<String>),
+    Chain(Vec<(String, String)>)
+}
+
+struct TreeNode {
+    label: String,
+    children: Vec<Self>
+}
+
+pub(crate) struct FamilyTree {
+    languages: Vec<String>,
+    transformations: Vec<(String, String, Branch)>
+}
+
+impl FamilyTree {
+    /// Loads every transformation in the family, since their validation languages aren't known until they're created.
+    pub(crate) fn load(family: &mut Family) -> Result<Self, ElbieError> {
+        let mut languages = family.language_keys();
+        let mut keys = family.transformation_keys();
+        keys.sort();
+
+        let mut transformations = Vec::new();
+        for (from, name) in keys {
+            let branch = if let Some(items) = family.transformation_set_contents(&from, &name)? {
+                Branch::Set(items.iter().map(|item| (*item).to_owned()).collect())
+            } else if let Some(steps) = family.transformation_chain_contents(&from, &name)? {
+                Branch::Chain(steps.to_vec())
+            } else {
+                family.load_transformation(&from, &name)?;
+                match family.get_transformation(&from, &name)? {
+                    TransformationEntry::Single(transformation) => Branch::Single(transformation.validation_language().map(ToOwned::to_owned)),
+                    TransformationEntry::Set(_) | TransformationEntry::Chain(_) => Branch::Single(None)
+                }
+            };
+            // the validation language might not be part of the family, but it still belongs on the tree.
+            if let Branch::Single(Some(target)) = &branch
+               && !languages.contains(target)
+            {
+                languages.push(target.clone());
+            }
+            transformations.push((from, name, branch));
+        }
+        languages.sort();
+
+        Ok(Self { languages,
+                  transformations })
+    }
+
+    fn language_index(&self, name: &str) -> Option<usize> {
+        self.languages.iter().position(|language| language == name)
+    }
+
+    fn transformation_index(&self, from: &str, name: &str) -> Option<usize> {
+        self.transformations.iter().position(|(source, transformation, _)| source == from && transformation == name)
+    }
+
+    fn to_dot(&self) -> String {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        // the kind of node is part of the id, so a language can't have the same id as a transformation.
+        let language_id = |name: &str| quote(&format!("L:{name}"));
+        let transformation_id = |from: &str, name: &str| quote(&format!("T:{from}/{name}"));
+
+        let mut result = String::new();
+        writeln!(result, "digraph family {{").expect("Could not write to DOT");
+        for language in &self.languages {
+            writeln!(result, "  {} [label={}, shape=box];", language_id(language), quote(language)).expect("Could not write to DOT");
+        }
+        for (from, name, branch) in &self.transformations {
+            let id = transformation_id(from, name);
+            let shape = match branch {
+                Branch::Single(_) => "ellipse",
+                Branch::Set(_) => "diamond",
+                Branch::Chain(_) => "hexagon"
+            };
+            writeln!(result, "  {id} [label={}, shape={shape}];", quote(name)).expect("Could not write to DOT");
+            writeln!(result, "  {} -> {id};", language_id(from)).expect("Could not write to DOT");
+            match branch {
+                Branch::Single(Some(target)) => writeln!(result, "  {id} -> {} [label=\"validates as\"];", language_id(target)).expect("Could not write to DOT"),
+                Branch::Single(None) => (),
+                Branch::Set(items) => {
+                    for item in items {
+                        writeln!(result, "  {id} -> {} [style=dashed];", transformation_id(from, item)).expect("Could not write to DOT");
+                    }
+                },
+                Branch::Chain(steps) => {
+                    for (i, (step_from, step_name)) in steps.iter().enumerate() {
+                        writeln!(result, "  {id} -> {} [style=dashed, label=\"{}\"];", transformation_id(step_from, step_name), i + 1).expect("Could not write to DOT");
+                    }
+                },
+            }
+        }
+        writeln!(result, "}}").expect("Could not write to DOT");
+        result
+    }
+
+    fn to_mermaid(&self) -> String {
+        // mermaid ids can't have most punctuation, so the nodes are numbered and the names go in the labels.
+        let quote = |text: &str| format!("\"{}\"", text.replace('"', "#quot;"));
+        let language_id = |name: &str| self.language_index(name).map(|i| format!("L{i}"));
+        let transformation_id = |from: &str, name: &str| self.transformation_index(from, name).map(|i| format!("T{i}"));
+
+        let mut result = String::new();
+        writeln!(result, "flowchart LR").expect("Could not write to Mermaid");
+        for (i, language) in self.languages.iter().enumerate() {
+            writeln!(result, "  L{i}[{}]", quote(language)).expect("Could not write to Mermaid");
+        }
+        for (i, (from, name, branch)) in self.transformations.iter().enumerate() {
+            let label = quote(name);
+            match branch {
+                Branch::Single(_) => writeln!(result, "  T{i}({label})"),
+                Branch::Set(_) => writeln!(result, "  T{i}{{{{{label}}}}}"),
+                Branch::Chain(_) => writeln!(result, "  T{i}[[{label}]]")
+            }.expect("Could not write to Mermaid");
+            if let Some(from_id) = language_id(from) {
+                writeln!(result, "  {from_id} --> T{i}").expect("Could not write to Mermaid");
+            }
+            match branch {
+                Branch::Single(Some(target)) => {
+                    if let Some(target_id) = language_id(target) {
+                        writeln!(result, "  T{i} -->|validates as| {target_id}").expect("Could not write to Mermaid");
+                    }
+                },
+                Branch::Single(None) => (),
+                Branch::Set(items) => {
+                    for item_id in items.iter().filter_map(|item| transformation_id(from, item)) {
+                        writeln!(result, "  T{i} -.-> {item_id}").expect("Could not write to Mermaid");
+                    }
+                },
+                Branch::Chain(steps) => {
+                    for (step, (step_from, step_name)) in steps.iter().enumerate() {
+                        if let Some(step_id) = transformation_id(step_from, step_name) {
+                            writeln!(result, "  T{i} -.->|{}| {step_id}", step + 1).expect("Could not write to Mermaid");
+                        }
+                    }
+                },
+            }
+        }
+        result
+    }
+
+    fn language_node(&self, language: &str, listed: &mut Vec<String>) -> TreeNode {
+        listed.push(language.to_owned());
+        let mut children = Vec::new();
+        for (from, name, branch) in &self.transformations {
+            if from != language {
+                continue;
+            }
+            children.push(match branch {
+                              Branch::Single(Some(target)) => {
+                                  if listed.contains(target) {
+                                      TreeNode { label: format!("{name} 🡺 {target} (see above)"),
+                                                 children: Vec::new() }
+                                  } else {
+                                      TreeNode { label: format!("{name} 🡺"),
+                                                 children: vec![self.language_node(target, listed)] }
+                                  }
+                              },
+                              Branch::Single(None) => TreeNode { label: name.clone(),
+                                                                 children: Vec::new() },
+                              Branch::Set(items) => TreeNode { label: format!("{name}: set of {}", items.join(", ")),
+                                                               children: Vec::new() },
+                              Branch::Chain(steps) => TreeNode { label: format!("{name}: chain of {}",
+                                                                                steps.iter().map(|(step_from, step_name)| format!("{step_from} 🡺 {step_name}")).collect::<Vec<_>>().join(", ")),
+                                                                 children: Vec::new() }
+                          });
+        }
+        TreeNode { label: language.to_owned(),
+                   children }
+    }
+
+    fn to_nodes(&self) -> Vec<TreeNode> {
+        let descendants = self.transformations
+                              .iter()
+                              .filter_map(|(_, _, branch)| match branch {
+                                  Branch::Single(target) => target.as_ref(),
+                                  Branch::Set(_) | Branch::Chain(_) => None
+                              })
+                              .collect::<Vec<_>>();
+
+        let mut listed = Vec::new();
+        let mut result = Vec::new();
+        for language in self.languages.iter().filter(|language| !descendants.contains(language)) {
+            result.push(self.language_node(language, &mut listed));
+        }
+        // anything left over is part of a cycle with no root.
+        for language in &self.languages {
+            if !listed.contains(language) {
+                result.push(self.language_node(language, &mut listed));
+            }
+        }
+        result
+    }
+
+    // the names could have characters which mean something in Markdown.
+    fn escape_markdown(text: &str) -> String {
+        let mut result = String::new();
+        for c in text.chars() {
+            if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+                result.push('\\');
+            }
+            result.push(c);
+        }
+        result
+    }
+
+    fn write_markdown(nodes: &[TreeNode], level: usize, output: &mut String) {
+        for node in nodes {
+            writeln!(output, "{}- {}", str::repeat("  ", level), Self::escape_markdown(&node.label)).expect("Could not write to Markdown");
+            Self::write_markdown(&node.children, level + 1, output);
+        }
+    }
+
+    fn write_html(nodes: &[TreeNode], parent: &mut html_builder::Node<'_>) {
+        let mut ul = parent.ul();
+        for node in nodes {
+            // safe escapes quotes as well as the markup characters, which the default doesn't.
+            let mut li = ul.li().safe();
+            write!(li, "{}", node.label).expect("Could not write to HTML");
+            if !node.children.is_empty() {
+                Self::write_html(&node.children, &mut li);
+            }
+        }
+    }
+
+    pub(crate) fn print(&self, format: &FamilyTreeFormat, output: &mut impl Write) -> Result<(), io::Error> {
+        match format {
+            FamilyTreeFormat::DOT => write!(output, "{}", self.to_dot()),
+            FamilyTreeFormat::Mermaid => write!(output, "{}", self.to_mermaid()),
+            FamilyTreeFormat::Markdown => {
+                let mut result = String::new();
+                Self::write_markdown(&self.to_nodes(), 0, &mut result);
+                write!(output, "{result}")
+            },
+            FamilyTreeFormat::HTML => {
+                let mut buffer = html_builder::Buffer::new();
+                let mut div = buffer.div().attr("class=\"elbie family-tree\"");
+                Self::write_html(&self.to_nodes(), &mut div);
+                writeln!(output, "{}", buffer.finish())
+            }
+        }
+    }
+}
@@ -57,7 +57,19 @@ pub(crate) enum TableClass {
     ElbiePhonemes,
     ElbieWords,
     ElbieOrthography,
-    ElbieLexicon
+    ElbieLexicon,
+    ElbieCollisions,
+    ElbieGlyphs,
+    ElbiePronunciation,
+    ElbieDerivation,
+    ElbieTransformStats,
+    ElbieExampleCheck,
+    ElbieAncestors,
+    ElbieTimeline,
+    ElbieCognates,
+    ElbieAdaptation,
+    ElbieCorrespondences,
+    ElbieIrregularCognates
 }
 
 impl Display for TableClass {
@@ -66,7 +78,19 @@ impl Display for TableClass {
             Self::ElbiePhonemes => write!(f, "elbie phonemes"),
             Self::ElbieWords => write!(f, "elbie generated-words"),
             Self::ElbieOrthography => write!(f, "elbie orthography"),
-            Self::ElbieLexicon => write!(f, "elbie lexicon")
+            Self::ElbieLexicon => write!(f, "elbie lexicon"),
+            Self::ElbieCollisions => write!(f, "elbie collisions"),
+            Self::ElbieGlyphs => write!(f, "elbie glyphs"),
+            Self::ElbiePronunciation => write!(f, "elbie pronunciation"),
+            Self::ElbieDerivation => write!(f, "elbie derivation"),
+            Self::ElbieTransformStats => write!(f, "elbie transform-stats"),
+            Self::ElbieExampleCheck => write!(f, "elbie example-check"),
+            Self::ElbieAncestors => write!(f, "elbie ancestors"),
+            Self::ElbieTimeline => write!(f, "elbie timeline"),
+            Self::ElbieCognates => write!(f, "elbie cognates"),
+            Self::ElbieAdaptation => write!(f, "elbie adaptation"),
+            Self::ElbieCorrespondences => write!(f, "elbie correspondences"),
+            Self::ElbieIrregularCognates => write!(f, "elbie irregular-cognates")
         }
     }
 }
@@ -434,7 +458,8 @@ impl SubrowHeader {
 #[derive(Debug)]
 pub(crate) struct Cell {
     text: String,
-    class: Option<TDClass>
+    class: Option<TDClass>,
+    html_attributes: Vec<String>
 }
 
 impl Cell {
@@ -444,7 +469,15 @@ impl Cell {
     pub(crate) fn content(text: String, class: Option<TDClass>) -> Self {
         assert!(!text.contains('\n'), "Grid cells must not contain newlines");
         Self { text,
-               class }
+               class,
+               html_attributes: Vec::new() }
+    }
+
+    /// Extra attributes for the cell in HTML output, such as `dir` for a native script.
+    #[must_use]
+    pub(crate) fn with_html_attributes(mut self, attributes: Vec<String>) -> Self {
+        self.html_attributes = attributes;
+        self
     }
 }
 
@@ -490,6 +523,7 @@ impl GridRow {
 pub(crate) struct Grid {
     class: TableClass,
     caption: String,
+    html_styles: Vec<String>,
     heads: Option<(Vec<ColumnHeader>, Option<Vec<SubcolumnHeader>>)>,
     body: Vec<GridRow>
 }
@@ -499,6 +533,7 @@ impl Grid {
     pub(crate) const fn new(class: TableClass, caption: String) -> Self {
         Self { caption,
                class,
+               html_styles: Vec::new(),
                heads: None,
                body: Vec::new() }
     }
@@ -508,6 +543,13 @@ impl Grid {
         &self.caption
     }
 
+    /// Adds CSS to embed before the table in HTML output, such as an `@font-face` rule. Duplicates are ignored.
+    pub(crate) fn add_html_style(&mut self, css: String) {
+        if !self.html_styles.contains(&css) {
+            self.html_styles.push(css);
+        }
+    }
+
     /// # Panics
     /// Function panics if column headers are already set
     pub(crate) fn set_headers(&mut self, row: Vec<ColumnHeader>) {
@@ -549,6 +591,9 @@ impl Grid {
         let column_header_offset = self.heads.len();
 
         let mut buffer = html_builder::Buffer::new();
+        if !self.html_styles.is_empty() {
+            write!(buffer.style().raw(), "{}", self.html_styles.join("\n")).expect("Could not write to html node");
+        }
         let mut table = buffer.table().attr(&format!("class=\"{}\"", self.class));
         write!(table.caption(), "{}", self.caption).expect("Could not write to html node");
 
@@ -591,13 +636,15 @@ impl Grid {
             }
 
             for Cell { text,
-                       class } in row.cells
+                       class,
+                       html_attributes } in row.cells
             {
-                let mut td = if let Some(class) = class {
+                let td = if let Some(class) = class {
                     tr.td().attr(&format!("class=\"{class}\""))
                 } else {
                     tr.td()
                 };
+                let mut td = html_attributes.iter().fold(td, |td, attribute| td.attr(attribute));
                 write!(&mut td, "{text}").expect("Could not write to html node.")
             }
         }
@@ -751,6 +798,7 @@ impl Grid {
 
         let Self { caption,
                    class,
+                   html_styles,
                    heads: head,
                    body } = self;
 
@@ -759,6 +807,7 @@ impl Grid {
                 // Don't blend if subheadings exist
                 Self { caption,
                        class,
+                       html_styles,
                        heads: Some((head, Some(subhead))),
                        body }
             },
@@ -766,6 +815,7 @@ impl Grid {
                 // Don't blend if there are no headings
                 Self { caption,
                        class,
+                       html_styles,
                        heads: None,
                        body }
             },
@@ -784,7 +834,8 @@ impl Grid {
                         let mut column_row = Vec::new();
                         for idx in 0..column_header.colspan.get() {
                             if let Some(Cell { text,
-                                               class: _ }) = cells_iter.next()
+                                               class: _,
+                                               html_attributes: _ }) = cells_iter.next()
                             {
                                 column_row.push(text)
                             } else {
@@ -815,6 +866,7 @@ impl Grid {
 
                 Self { class,
                        caption,
+                       html_styles,
                        heads: Some((new_headers, None)),
                        body: new_rows }
             }
@@ -824,6 +876,7 @@ impl Grid {
     fn blend_row_groups(self) -> Self {
         let Self { caption,
                    class,
+                   html_styles,
                    heads,
                    body } = self;
 
@@ -863,6 +916,7 @@ impl Grid {
 
         Self { caption,
                class,
+               html_styles,
                heads,
                body: new_body }
     }
@@ -989,7 +1043,8 @@ impl Grid {
             }
 
             for Cell { text,
-                       class: _ } in &body_row.cells
+                       class: _,
+                       html_attributes: _ } in &body_row.cells
             {
                 row.add_cell(PrettyCell::new(text))
             }
@@ -33,29 +33,22 @@ use crate::phonotactics::EnvironmentChoice;
 use crate::phonotactics::PatternBuilder;
 use crate::phonotactics::PatternSet;
 use crate::phonotactics::TreeBranchesBuilder;
+use crate::respelling::describe;
+use crate::respelling::english_equivalent;
+use crate::respelling::respell;
+use crate::script::Script;
+use crate::text::TextStyle;
+use crate::transformation::Transformation;
 use crate::validation::ValidWordElement;
 use crate::validation::ValidationTraceCallback;
 use crate::word::Word;
 use crate::word_table::WordTable;
-use core::cmp::Ordering;
 use core::iter;
 use core::iter::Peekable;
 use core::slice::Iter;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::rc::Rc;
-use unicode_normalization::UnicodeNormalization as _;
-
-fn sort_phonemes_by_length_descending(a: &Rc<Phoneme>, b: &Rc<Phoneme>) -> Ordering {
-    let name_a = a.name;
-    let len_a = name_a.len();
-    let name_b = b.name;
-    let len_b = name_b.len();
-    if len_a == len_b {
-        name_a.partial_cmp(name_b).expect("Can't order phoneme names for some reason.")
-    } else {
-        len_b.partial_cmp(&len_a).expect("Can't order phoneme lengths for some reason.")
-    }
-}
 
 #[derive(Debug)]
 pub struct Language {
@@ -67,11 +60,17 @@ pub struct Language {
     // (such as temporary phonemes during transformations)
     phoneme_behavior: HashMap<Rc<Phoneme>, PhonemeBehavior>,
     orthographies: Vec<&'static str>,
+    text_styles: Vec<TextStyle>,
+    scripts: Vec<Option<Script>>,
+    respelling_orthography: Option<usize>,
+    orthography_transformations: Vec<Option<Transformation>>,
     #[allow(deprecated)]
     patterns: PatternSet,
     tables: Vec<TableEntry>,
     analysis_cluster_sets: Option<Vec<&'static str>>,
-    analysis_structure_sets: Option<Vec<&'static str>>
+    analysis_structure_sets: Option<Vec<&'static str>>,
+    allophony: Option<Transformation>,
+    epenthetic_set: Option<&'static str>
 }
 
 impl Language {
@@ -88,14 +87,23 @@ impl Language {
         });
         let analysis_cluster_sets = None;
         let analysis_structural_sets = None;
+        let text_styles = orthographies.iter().map(|_| TextStyle::default()).collect();
+        let scripts = orthographies.iter().map(|_| None).collect();
+        let orthography_transformations = orthographies.iter().map(|_| None).collect();
         Self { name,
                inventory,
                phoneme_behavior,
                orthographies,
+               text_styles,
+               scripts,
+               respelling_orthography: None,
+               orthography_transformations,
                patterns,
                tables,
                analysis_cluster_sets,
-               analysis_structure_sets: analysis_structural_sets }
+               analysis_structure_sets: analysis_structural_sets,
+               allophony: None,
+               epenthetic_set: None }
     }
 
     pub fn with_pattern<Pattern: Fn(&mut PatternBuilder)>(name: &'static str, orthographies: Vec<&'static str>, initial_pattern: Pattern) -> Self {
@@ -105,14 +113,23 @@ impl Language {
         let patterns = PatternSet::new(initial_pattern);
         let analysis_cluster_sets = None;
         let analysis_structural_sets = None;
+        let text_styles = orthographies.iter().map(|_| TextStyle::default()).collect();
+        let scripts = orthographies.iter().map(|_| None).collect();
+        let orthography_transformations = orthographies.iter().map(|_| None).collect();
         Self { name,
                inventory,
                phoneme_behavior,
                orthographies,
+               text_styles,
+               scripts,
+               respelling_orthography: None,
+               orthography_transformations,
                patterns,
                tables,
                analysis_cluster_sets,
-               analysis_structure_sets: analysis_structural_sets }
+               analysis_structure_sets: analysis_structural_sets,
+               allophony: None,
+               epenthetic_set: None }
     }
 
     #[must_use]
@@ -135,6 +152,76 @@ impl Language {
         &self.orthographies
     }
 
+    /// Sets how running text is capitalized and separated for the orthography at the specified index. By default, sentences and proper nouns are capitalized, words are separated by spaces and morphemes are not separated.
+    pub fn set_text_style(&mut self, orthography: usize, style: TextStyle) -> Result<(), ElbieError> {
+        let entry = self.text_styles.get_mut(orthography).ok_or(ElbieError::UnknownOrthography(orthography))?;
+        *entry = style;
+        Ok(())
+    }
+
+    pub(crate) fn text_style(&self, orthography: usize) -> Option<&TextStyle> {
+        self.text_styles.get(orthography)
+    }
+
+    /// Marks the orthography at the specified index as being written in a native script. See `Script`.
+    pub fn set_script(&mut self, orthography: usize, script: Script) -> Result<(), ElbieError> {
+        let entry = self.scripts.get_mut(orthography).ok_or(ElbieError::UnknownOrthography(orthography))?;
+        *entry = Some(script);
+        Ok(())
+    }
+
+    pub(crate) fn script(&self, orthography: usize) -> Option<&Script> {
+        self.scripts.get(orthography).and_then(Option::as_ref)
+    }
+
+    /// Adds an orthography which respells each phoneme with English-friendly letters, based on the IPA symbols in the phoneme (/ʃ/ is "sh", /ɛ/ is "eh"). Phonemes don't need to supply a spelling for this orthography, so it can be added at any time. Returns the index of the new orthography.
+    pub fn add_respelling_orthography(&mut self, name: &'static str) -> Result<usize, ElbieError> {
+        if self.respelling_orthography.is_some() {
+            return Err(ElbieError::RespellingOrthographyAlreadyAdded);
+        }
+        let index = self.orthographies.len();
+        self.orthographies.push(name);
+        self.text_styles.push(TextStyle::default());
+        self.scripts.push(None);
+        self.orthography_transformations.push(None);
+        self.respelling_orthography = Some(index);
+        Ok(index)
+    }
+
+    /**
+    Adds an orthography which is spelled by a transformation, instead of by the spellings of each phoneme. This gives spelling all of the context available to transformation rules. Phonemes don't need to supply a spelling for this orthography, so it can be added at any time. Returns the index of the new orthography.
+
+    Create the transformation with `Transformation::from` this language, then use `add_inventory` to add an inventory of graphemes for the rules to replace phonemes with. The spelling is the names of the resulting "phonemes" joined together, so phonemes which aren't replaced are spelled as themselves. If the rules give more than one variant, the first is used.
+    */
+    pub fn add_transformed_orthography(&mut self, name: &'static str, transformation: Transformation) -> usize {
+        let index = self.orthographies.len();
+        self.orthographies.push(name);
+        self.text_styles.push(TextStyle::default());
+        self.scripts.push(None);
+        self.orthography_transformations.push(Some(transformation));
+        index
+    }
+
+    pub(crate) fn orthography_transformation(&self, orthography: usize) -> Option<&Transformation> {
+        self.orthography_transformations.get(orthography).and_then(Option::as_ref)
+    }
+
+    /// Creates a cell for spelled text, which will be displayed in the orthography's script in HTML. Any font needed for the script is added to the grid.
+    pub(crate) fn spelling_cell(&self, grid: &mut Grid, orthography: usize, text: String) -> Cell {
+        let cell = Cell::content(text, None);
+        if let Some(script) = self.script(orthography) {
+            script.style_cell(grid, cell)
+        } else {
+            cell
+        }
+    }
+
+    /// Finds the index of an orthography by name. Case is ignored.
+    pub(crate) fn find_orthography(&self, name: &str) -> Result<usize, ElbieError> {
+        let name_lower = name.to_lowercase();
+        self.orthographies.iter().position(|orthography| orthography.to_lowercase() == name_lower).ok_or_else(|| ElbieError::UnknownOrthographyName(name.to_owned()))
+    }
+
     pub(crate) const fn tables(&self) -> &Vec<TableEntry> {
         &self.tables
     }
@@ -151,9 +238,14 @@ impl Language {
         &self.patterns
     }
 
+    // The respelling orthography doesn't need spellings from the phonemes.
+    fn spelled_orthography_count(&self) -> usize {
+        self.orthographies.len() - usize::from(self.respelling_orthography.is_some()) - self.orthography_transformations.iter().filter(|transformation| transformation.is_some()).count()
+    }
+
     fn add_phoneme_to_inventory(&mut self, phoneme: &'static str, sets: &[&'static str], behavior: PhonemeBehavior) -> Result<Rc<Phoneme>, ElbieError> {
-        if behavior.spelling_len() != self.orthographies().len() {
-            return Err(ElbieError::MismatchedSpellingsForPhoneme(phoneme, self.orthographies.len(), behavior.spelling_len()));
+        if behavior.spelling_len() != self.spelled_orthography_count() {
+            return Err(ElbieError::MismatchedSpellingsForPhoneme(phoneme, self.spelled_orthography_count(), behavior.spelling_len()));
         }
 
         let phoneme = self.inventory.add_phoneme(phoneme, sets)?;
@@ -179,11 +271,21 @@ impl Language {
         Ok(self.inventory.get_set(set)?.items())
     }
 
-    /// # Panics
-    /// Panics if requested orthography index is out of range
-    pub(crate) fn spell_phoneme(&self, phoneme: &Rc<Phoneme>, orthography: usize, result: &mut String, next: Option<&mut Peekable<Iter<Rc<Phoneme>>>>) {
+    /// Spells one phoneme, appending it to the result. If the orthography is made by a transformation, the phoneme is spelled on its own, without the rest of the word for the rules to match, so words should always be spelled with `spell_word`.
+    pub(crate) fn spell_phoneme(&self, phoneme: &Rc<Phoneme>, orthography: usize, result: &mut String, next: Option<&mut Peekable<Iter<Rc<Phoneme>>>>) -> Result<(), ElbieError> {
         if orthography >= self.orthographies.len() {
-            panic!("Language only has {} orthographies.", self.orthographies.len())
+            return Err(ElbieError::UnknownOrthography(orthography));
+        }
+
+        if self.respelling_orthography == Some(orthography) {
+            result.push_str(&respell(phoneme.name));
+            return Ok(());
+        }
+
+        if let Some(transformation) = self.orthography_transformation(orthography) {
+            // on its own, the phoneme has no context for the rules, so this is only useful for tables.
+            result.push_str(&Self::spell_transformed(transformation, &Word::from(vec![phoneme.clone()]))?);
+            return Ok(());
         }
 
         match self.phoneme_behavior.get(phoneme).and_then(|b| b.spelling().get(orthography)) {
@@ -191,16 +293,25 @@ impl Language {
             Some(SpellingBehavior::Text(text)) => result.push_str(text),
             Some(SpellingBehavior::Callback(callback)) => callback(self, phoneme, result, next)
         }
+        Ok(())
     }
 
-    #[must_use]
-    pub(crate) fn spell_word(&self, word: &Word, orthography: usize) -> String {
+    // The graphemes are phonemes in the transformation's inventory, so the spelling is just their names.
+    fn spell_transformed(transformation: &Transformation, word: &Word) -> Result<String, ElbieError> {
+        Ok(transformation.transform(word, &HashMap::new(), None)?.first().map_or_else(String::new, Word::to_raw_string))
+    }
+
+    pub(crate) fn spell_word(&self, word: &Word, orthography: usize) -> Result<String, ElbieError> {
+        if let Some(transformation) = self.orthography_transformation(orthography) {
+            return Self::spell_transformed(transformation, word);
+        }
+
         let mut result = String::new();
         let mut iter = word.phonemes().iter().peekable();
         while let Some(phoneme) = iter.next() {
-            self.spell_phoneme(phoneme, orthography, &mut result, Some(&mut iter))
+            self.spell_phoneme(phoneme, orthography, &mut result, Some(&mut iter))?
         }
-        result
+        Ok(result)
     }
 
     // will eventually be used over add_difference
@@ -280,34 +391,46 @@ impl Language {
         self.patterns().generate(self, &mut rng)
     }
 
+    /// Returns every valid word up to the specified length. The number of words grows very quickly with the length, so keep it small.
+    pub(crate) fn enumerate_words(&self, max_length: usize) -> Result<Vec<Word>, ElbieError> {
+        let mut seen = HashSet::new();
+        let mut result = Vec::new();
+        for word in self.patterns().enumerate(self, max_length)? {
+            // enumeration isn't greedy the way validation is, so some of these might not actually validate.
+            if seen.insert(word.clone()) && self.check_word(&word, None)?.is_ok() {
+                result.push(word);
+            }
+        }
+        Ok(result)
+    }
+
     pub fn read_word(&self, input: &str) -> Result<Word, ElbieError> {
-        // not an efficient algorithm, but it works...
-        // FUTURE: This should be "cached" somehow to speed up the process. Perhaps by using a BTreeMap instead of a HashMap, and forcing insertion in order when adding phonemes to it.
-        let mut phonemes: Vec<Rc<Phoneme>> = self.inventory.phonemes().values().cloned().collect();
-        // sort the phonemes so that longer phonemes come first. This should avoid longer graphemes from matching the shorter graphemes accidentally. For example, say there were phonemes "aw" and "a". If "aw" is sorted first for the match, then if it's found in the word it won't be mistaken for an "a" followed by a "w".
-        phonemes.sort_by(sort_phonemes_by_length_descending);
+        self.inventory.read_word(input)
+    }
 
-        let mut word: Vec<Rc<Phoneme>> = vec![];
+    /**
+    Sets the allophonic rules which turn a phonemic word into a narrow phonetic transcription. These are synchronic rules, like aspiration of word-initial stops or nasalization of vowels before nasals, so they don't make a new language and their results aren't validated.
 
-        let mut source = if self.inventory.normalize_phonemes() {
-            &input.nfd().collect::<String>()
-        } else {
-            input
-        };
+    Create the transformation with `Transformation::from` this language, then use `add_inventory` to add the allophones which aren't phonemes of the language.
+    */
+    pub fn set_allophony(&mut self, allophony: Transformation) {
+        self.allophony = Some(allophony);
+    }
 
-        'outer: while !source.is_empty() {
-            for phoneme in &phonemes {
-                let name = phoneme.name;
-                if let Some(after) = source.strip_prefix(name) {
-                    word.push((*phoneme).clone()); // clone twice because apparently phoneme is a double reference
-                    source = after;
-                    continue 'outer;
-                }
-            }
-            return Err(ElbieError::UnknownPhonemeWhileReading(input.to_owned(), source.to_owned()));
-        }
+    #[must_use]
+    pub const fn allophony(&self) -> Option<&Transformation> {
+        self.allophony.as_ref()
+    }
+
+    /// Returns the narrow phonetic transcriptions of the word, or None if the language has no allophonic rules. There is more than one result if any of the rules are optional.
+    pub fn phonetic_transcription(&self, word: &Word) -> Result<Option<Vec<Word>>, ElbieError> {
+        self.allophony.as_ref().map(|allophony| allophony.transform(word, &HashMap::new(), None)).transpose()
+    }
 
-        Ok(Word::new(&word))
+    /// Formats the phonetic transcriptions of the word in square brackets. Returns an error if the language has no allophonic rules.
+    pub(crate) fn phonetic_string(&self, word: &Word) -> Result<String, ElbieError> {
+        let variants = self.phonetic_transcription(word)?.ok_or(ElbieError::NoAllophony(self.name))?;
+        Ok(variants.iter().map(|variant| variant.to_phonetic_display().to_string()).collect::<Vec<_>>().join(" ~ "))
     }
 
     pub(crate) fn check_word(&self, word: &Word, trace: Option<&ValidationTraceCallback>) -> Result<Result<Vec<ValidWordElement>, ()>, ElbieError> {
@@ -432,8 +555,8 @@ impl Language {
                     row.push_cell(Cell::content(phoneme.to_string(), None));
                     for i in 0..self.orthographies.len() {
                         let mut cell = String::new();
-                        self.spell_phoneme(phoneme, i, &mut cell, None);
-                        row.push_cell(Cell::content(cell, None));
+                        self.spell_phoneme(phoneme, i, &mut cell, None)?;
+                        row.push_cell(self.spelling_cell(&mut grid, i, cell));
                     }
                 } else {
                     // add blank cells to make the table rectangular.
@@ -450,16 +573,87 @@ impl Language {
         Ok(grid)
     }
 
-    pub(crate) fn load_lexicon(&self, words: &WordTable, primary_orthography: usize, style: &LexiconStyle) -> Result<Lexicon, ElbieError> {
+    /// Builds a table of the glyphs for an orthography written in a native script, or None if the orthography doesn't have one.
+    pub(crate) fn display_glyphs(&self, orthography: usize) -> Result<Option<Grid>, ElbieError> {
+        let name = self.orthographies.get(orthography).ok_or(ElbieError::UnknownOrthography(orthography))?;
+        let Some(script) = self.script(orthography) else {
+            return Ok(None);
+        };
+
+        let phonemes = self.inventory.get_set(PHONEME)?.clone().to_vec();
+
+        let mut grid = Grid::new(TableClass::ElbieGlyphs, format!("Glyphs for {name}"));
+        grid.set_headers(vec![ColumnHeader::new("Glyph".to_owned(), 1),
+                              ColumnHeader::new("Code Points".to_owned(), 1),
+                              ColumnHeader::new("Name".to_owned(), 1),
+                              ColumnHeader::new("Phonemes".to_owned(), 1),
+                              ColumnHeader::new("Notes".to_owned(), 1)]);
+
+        for glyph in script.glyphs() {
+            let code_points = glyph.text().chars().map(|c| format!("U+{:04X}", u32::from(c))).collect::<Vec<_>>().join(" ");
+            // This only finds phonemes which are spelled with exactly the glyph. Callbacks could spell them differently in context.
+            let mut spelled = Vec::new();
+            for phoneme in &phonemes {
+                let mut spelling = String::new();
+                self.spell_phoneme(phoneme, orthography, &mut spelling, None)?;
+                if spelling == glyph.text() {
+                    spelled.push(phoneme.to_string());
+                }
+            }
+            let spelled = spelled.join(", ");
+
+            let mut row = GridRow::new(TRBodyClass::BodyRow);
+            row.push_cell(self.spelling_cell(&mut grid, orthography, glyph.text().to_owned()));
+            row.push_cell(Cell::content(code_points, None));
+            row.push_cell(Cell::content(glyph.name().to_owned(), None));
+            row.push_cell(Cell::content(spelled, None));
+            row.push_cell(Cell::content(glyph.notes().to_owned(), None));
+            grid.push_body_row(row);
+        }
+
+        Ok(Some(grid))
+    }
+
+    /// Builds a pronunciation guide for readers who don't know IPA, describing each phoneme with a respelling and approximate English equivalents.
+    pub(crate) fn display_pronunciation_guide(&self) -> Result<Grid, ElbieError> {
+        let phonemes = self.inventory.get_set(PHONEME)?.clone().to_vec();
+
+        let mut grid = Grid::new(TableClass::ElbiePronunciation, format!("Pronunciation Guide for {}", self.name));
+        grid.set_headers(vec![ColumnHeader::new("Phoneme".to_owned(), 1),
+                              ColumnHeader::new("Respelling".to_owned(), 1),
+                              ColumnHeader::new("Description".to_owned(), 1),
+                              ColumnHeader::new("Sounds Like".to_owned(), 1)]);
+
+        for phoneme in phonemes {
+            let mut row = GridRow::new(TRBodyClass::BodyRow);
+            row.push_cell(Cell::content(phoneme.to_string(), None));
+            row.push_cell(Cell::content(respell(phoneme.name), None));
+            row.push_cell(Cell::content(describe(&self.inventory, &phoneme), None));
+            row.push_cell(Cell::content(english_equivalent(phoneme.name), None));
+            grid.push_body_row(row);
+        }
+
+        Ok(grid)
+    }
+
+    pub(crate) fn load_lexicon(&self, words: &WordTable, primary_orthographies: Vec<usize>, style: &LexiconStyle, phonetic: bool) -> Result<Lexicon, ElbieError> {
         let definition_field = words.find_attribute(|a| a.to_lowercase() == "definition").ok_or(ElbieError::NoDefinitionFieldFound)?;
 
-        let mut result = Lexicon::new(style, self.orthographies.clone(), primary_orthography);
+        // without allophonic rules, there's no phonetic transcription to show.
+        let phonetic = phonetic && self.allophony.is_some();
+
+        let mut result = Lexicon::new(style, self.orthographies.clone(), self.scripts.clone(), primary_orthographies, phonetic)?;
 
         for (row, entry) in words.entries().enumerate() {
             let word = &entry.word();
             let word = self.read_word(word).map_err(|e| ElbieError::LexiconParsingError(row, e.into()))?;
-            let spelling = (0..self.orthographies.len()).map(|i| self.spell_word(&word, i)).collect();
-            let entry = LexiconEntry::new(word, spelling, entry.get_attribute(definition_field).ok_or(ElbieError::NoDefinitionFoundAt(row))?.to_owned());
+            let spelling = (0..self.orthographies.len()).map(|i| self.spell_word(&word, i)).collect::<Result<_, _>>()?;
+            let phonetic = if phonetic {
+                Some(self.phonetic_string(&word)?)
+            } else {
+                None
+            };
+            let entry = LexiconEntry::new(word, phonetic, spelling, entry.get_attribute(definition_field).ok_or(ElbieError::NoDefinitionFoundAt(row))?.to_owned());
 
             result.push_entry(entry);
         }
@@ -495,11 +689,20 @@ impl Language {
     pub(crate) const fn analysis_structure_sets(&self) -> Option<&Vec<&'static str>> {
         self.analysis_structure_sets.as_ref()
     }
+
+    /// Sets the phonemes which can be inserted when a loanword is adapted into this language, usually one or two vowels. Without this, any phoneme can be inserted, and there will be a lot of equally good adaptations. See `Family::adapt_word`.
+    pub const fn set_epenthetic_set(&mut self, set: &'static str) {
+        self.epenthetic_set = Some(set)
+    }
+
+    pub(crate) fn epenthetic_set(&self) -> &'static str {
+        self.epenthetic_set.unwrap_or(PHONEME)
+    }
 }
 
 impl InventoryLoader for Language {
     fn add_phoneme(&mut self, phoneme: &'static str, sets: &[&'static str]) -> Result<Rc<Phoneme>, ElbieError> {
-        self.add_phoneme_to_inventory(phoneme, sets, PhonemeBehavior::new(iter::repeat_with(SpellingBehavior::default).take(self.orthographies.len()).collect()))
+        self.add_phoneme_to_inventory(phoneme, sets, PhonemeBehavior::new(iter::repeat_with(SpellingBehavior::default).take(self.spelled_orthography_count()).collect()))
     }
 
     fn add_difference(&mut self, name: &'static str, base_set: &'static str, exclude_sets: &[&'static str]) -> Result<(), ElbieError> {
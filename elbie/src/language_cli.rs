@@ -1,3 +1,4 @@
+use crate::cli_functions::OrthographyIndex;
 use crate::cli_functions::ValidateOption;
 use crate::cli_functions::format_lexicon;
 use crate::cli_functions::generate_words;
@@ -166,21 +167,21 @@ pub fn run<ArgItem: AsRef<str>, Args: Iterator<Item = ArgItem>>(args: &mut Args,
     match language {
         Ok(language) => {
             match arguments.command {
-                Command::GenerateWords(count) => generate_words(arguments.grid_style.as_ref(), &language, count, output),
+                Command::GenerateWords(count) => generate_words(arguments.grid_style.as_ref(), &language, count, false, output),
                 Command::ValidateWords(words, option) => {
                     let mut words_data = WordTable::default();
                     words_data.add_words(&words);
-                    validate_words(&language, words_data, &option, &Format::Plain, output)
+                    validate_words(&language, words_data, &option, false, &Format::Plain, output)
                 },
                 Command::ShowPhonemes(table) => show_phonemes(arguments.grid_style.as_ref(), &language, table.as_ref(), output),
-                Command::ShowSpelling(columns) => show_spelling(arguments.grid_style.as_ref(), &language, columns, output),
+                Command::ShowSpelling(columns) => show_spelling(arguments.grid_style.as_ref(), &language, columns, false, output),
                 Command::ProcessLexicon(path, ortho_index) => {
                     // NOTE: I'm doing an expect here because this whole 'run' function is deprecated anyway, so I'm not going to change it's signature.
                     let Ok(words_data) = WordTable::read(&path) else {
                         eprintln!("!!! Couldn't read input lexicon");
                         process::exit(1);
                     };
-                    format_lexicon(arguments.grid_style.as_ref().unwrap_or(&Format::Plain), &LexiconStyle::List, &language, &words_data, ortho_index, output)
+                    format_lexicon(arguments.grid_style.as_ref().unwrap_or(&Format::Plain), &LexiconStyle::List, &language, &words_data, &[OrthographyIndex::Index(ortho_index)], false, output)
                 },
                 Command::ShowUsage => {
                     let exe_name = env::current_exe().ok().as_deref().and_then(Path::file_name).map(OsStr::display).as_ref().map(ToString::to_string);
@@ -1,3 +1,4 @@
+use crate::errors::ElbieError;
 use crate::format::Format;
 use crate::grid::Cell;
 use crate::grid::ColumnHeader;
@@ -6,10 +7,12 @@ use crate::grid::GridRow;
 use crate::grid::TRBodyClass;
 use crate::grid::TableClass;
 use crate::grid::TableOutput;
+use crate::script::Script;
 use crate::word::Word;
 use core::fmt::Write as _;
 use core::str::FromStr;
 use html_builder::Html5 as _;
+use std::collections::HashSet;
 use std::io;
 use std::io::Write;
 
@@ -32,77 +35,105 @@ impl FromStr for LexiconStyle {
 
 pub(crate) struct LexiconEntry {
     word: Word,
+    phonetic: Option<String>,
     spelling: Vec<String>,
     definition: String
 }
 
 impl LexiconEntry {
-    pub(crate) const fn new(word: Word, spelling: Vec<String>, definition: String) -> Self {
+    pub(crate) const fn new(word: Word, phonetic: Option<String>, spelling: Vec<String>, definition: String) -> Self {
         Self { word,
+               phonetic,
                spelling,
                definition }
     }
 }
 
+/// Splits the items into the primary items, in the order they were requested, and the rest.
+fn split_primary<Item: Copy>(items: &[Item], primary_orthographies: &[usize]) -> (Vec<Item>, Vec<Item>) {
+    let primary = primary_orthographies.iter().filter_map(|i| items.get(*i).copied()).collect();
+    let other = items.iter().enumerate().filter(|(i, _)| !primary_orthographies.contains(i)).map(|(_, item)| *item).collect();
+    (primary, other)
+}
+
+// also removes duplicates, so an orthography isn't listed twice.
+fn check_primary_orthographies(orthographies: &[&'static str], primary_orthographies: &mut Vec<usize>) -> Result<(), ElbieError> {
+    let mut seen = HashSet::new();
+    primary_orthographies.retain(|i| seen.insert(*i));
+    if primary_orthographies.is_empty() {
+        return Err(ElbieError::NoPrimaryOrthography);
+    }
+    if let Some(index) = primary_orthographies.iter().find(|i| **i >= orthographies.len()) {
+        return Err(ElbieError::UnknownOrthography(*index));
+    }
+    Ok(())
+}
+
 pub(crate) struct LexiconTable {
     grid: Grid,
-    primary_orthography_idx: usize
+    scripts: Vec<Option<Script>>,
+    primary_orthographies: Vec<usize>,
+    phonetic: bool
 }
 
 impl LexiconTable {
-    pub(crate) fn new(orthographies: &[&'static str], primary_orthography_idx: usize) -> Self {
+    pub(crate) fn new(orthographies: &[&'static str], scripts: Vec<Option<Script>>, mut primary_orthographies: Vec<usize>, phonetic: bool) -> Result<Self, ElbieError> {
+        check_primary_orthographies(orthographies, &mut primary_orthographies)?;
+
         let mut grid = Grid::new(TableClass::ElbieLexicon, "Lexicon".to_owned());
 
-        let mut primary_orthography = None;
-        let mut other_orthographies = Vec::new();
-        for (i, orthography) in orthographies.iter().enumerate() {
-            if i == primary_orthography_idx {
-                primary_orthography = Some(orthography)
-            } else {
-                other_orthographies.push(*orthography);
-            }
-        }
-        let primary_orthography = primary_orthography.expect("Primary orthography index was out of bounds");
+        let (primary, other) = split_primary(orthographies, &primary_orthographies);
 
         let mut headers = Vec::new();
-        headers.push(ColumnHeader::new((*primary_orthography).to_owned(), 1));
+        for orthography in primary {
+            headers.push(ColumnHeader::new(orthography.to_owned(), 1));
+        }
         headers.push(ColumnHeader::new("Word".to_owned(), 1));
-        for orthography in &other_orthographies {
-            headers.push(ColumnHeader::new((*orthography).to_owned(), 1));
+        if phonetic {
+            headers.push(ColumnHeader::new("Phonetic".to_owned(), 1));
+        }
+        for orthography in other {
+            headers.push(ColumnHeader::new(orthography.to_owned(), 1));
         }
         headers.push(ColumnHeader::new("Definition".to_owned(), 1));
 
         grid.set_headers(headers);
 
-        Self { grid,
-               primary_orthography_idx }
+        Ok(Self { grid,
+                  scripts,
+                  primary_orthographies,
+                  phonetic })
     }
 
-    pub(crate) fn push_entry(&mut self, entry: LexiconEntry) {
-        let mut primary_spelling = None;
-        let mut other_spellings = Vec::new();
-        for (i, spelling) in entry.spelling.iter().enumerate() {
-            if i == self.primary_orthography_idx {
-                primary_spelling = Some(spelling)
-            } else {
-                other_spellings.push(spelling);
-            }
+    fn spelling_cell(&mut self, spellings: &[String], orthography: usize) -> Cell {
+        let cell = Cell::content(spellings.get(orthography).cloned().unwrap_or_default(), None);
+        if let Some(Some(script)) = self.scripts.get(orthography) {
+            script.style_cell(&mut self.grid, cell)
+        } else {
+            cell
         }
-        let primary_spelling = primary_spelling.expect("Primary orthography index was out of bounds");
+    }
+
+    pub(crate) fn push_entry(&mut self, entry: LexiconEntry) {
+        let orthographies = (0..entry.spelling.len()).collect::<Vec<_>>();
+        let (primary, other) = split_primary(&orthographies, &self.primary_orthographies);
 
         let mut fields = GridRow::new(TRBodyClass::BodyRow);
-        fields.push_cell(Cell::content((*primary_spelling).clone(), None));
+        for orthography in primary {
+            fields.push_cell(self.spelling_cell(&entry.spelling, orthography));
+        }
         fields.push_cell(Cell::content(entry.word.to_string(), None));
-        for spelling in &other_spellings {
-            fields.push_cell(Cell::content((*spelling).to_owned(), None));
+        if self.phonetic {
+            fields.push_cell(Cell::content(entry.phonetic.unwrap_or_default(), None));
+        }
+        for orthography in other {
+            fields.push_cell(self.spelling_cell(&entry.spelling, orthography));
         }
         fields.push_cell(Cell::content(entry.definition, None));
 
         self.grid.push_body_row(fields);
     }
 
-    /// # Panics
-    /// panics if the `self.primary_orthography' is not a valid index in the supplied orthographies.
     #[must_use]
     pub(crate) fn into_output(self, style: &Format) -> TableOutput {
         self.grid.into_output(style)
@@ -110,9 +141,10 @@ impl LexiconTable {
 }
 
 trait LexiconWriter {
-    fn initialize(main_orthography: &'static str, orthographies: Vec<&'static str>) -> Self;
+    // the scripts are in the same order as the orthographies
+    fn initialize(main_orthographies: Vec<&'static str>, orthographies: Vec<&'static str>, main_scripts: Vec<Option<&Script>>, scripts: Vec<Option<&Script>>) -> Self;
 
-    fn write_entry(&mut self, main_spelling: &str, other_spellings: &[&str], word: &Word, definition: &str, output: &mut String);
+    fn write_entry(&mut self, main_spellings: &[&str], other_spellings: &[&str], word: &Word, phonetic: Option<&str>, definition: &str, output: &mut String);
 }
 
 struct PlainLexiconWriter {
@@ -120,12 +152,15 @@ struct PlainLexiconWriter {
 }
 
 impl LexiconWriter for PlainLexiconWriter {
-    fn initialize(_: &'static str, orthographies: Vec<&'static str>) -> Self {
+    fn initialize(_: Vec<&'static str>, orthographies: Vec<&'static str>, _: Vec<Option<&Script>>, _: Vec<Option<&Script>>) -> Self {
         Self { orthographies }
     }
 
-    fn write_entry(&mut self, main_spelling: &str, other_spellings: &[&str], word: &Word, definition: &str, output: &mut String) {
-        write!(output, "{main_spelling} ({word}").expect("Could not write to Plain Text");
+    fn write_entry(&mut self, main_spellings: &[&str], other_spellings: &[&str], word: &Word, phonetic: Option<&str>, definition: &str, output: &mut String) {
+        write!(output, "{} ({word}", main_spellings.join(", ")).expect("Could not write to Plain Text");
+        if let Some(phonetic) = phonetic {
+            write!(output, " {phonetic}").expect("Could not write to Plain Text");
+        }
         for (orthography, spelling) in self.orthographies.iter().zip(other_spellings) {
             write!(output, "; {orthography}: {spelling}").expect("Could not write to Plain Text");
         }
@@ -138,12 +173,16 @@ struct MarkdownLexiconWriter {
 }
 
 impl LexiconWriter for MarkdownLexiconWriter {
-    fn initialize(_: &'static str, orthographies: Vec<&'static str>) -> Self {
+    fn initialize(_: Vec<&'static str>, orthographies: Vec<&'static str>, _: Vec<Option<&Script>>, _: Vec<Option<&Script>>) -> Self {
         Self { orthographies }
     }
 
-    fn write_entry(&mut self, main_spelling: &str, other_spellings: &[&str], word: &Word, definition: &str, output: &mut String) {
-        write!(output, "**{main_spelling}**. ({word}").expect("Could not write to Markdown");
+    fn write_entry(&mut self, main_spellings: &[&str], other_spellings: &[&str], word: &Word, phonetic: Option<&str>, definition: &str, output: &mut String) {
+        let main_spellings = main_spellings.iter().map(|spelling| format!("**{spelling}**")).collect::<Vec<_>>();
+        write!(output, "{}. ({word}", main_spellings.join(", ")).expect("Could not write to Markdown");
+        if let Some(phonetic) = phonetic {
+            write!(output, " {phonetic}").expect("Could not write to Markdown");
+        }
         for (orthography, spelling) in self.orthographies.iter().zip(other_spellings) {
             write!(output, "; {orthography}: *{spelling}*").expect("Could not write to Markdown");
         }
@@ -154,22 +193,38 @@ impl LexiconWriter for MarkdownLexiconWriter {
 }
 
 struct HTMLLexiconWriter {
-    orthographies: Vec<&'static str>
+    orthographies: Vec<&'static str>,
+    // the attributes for the script of each orthography, see `Script::html_attributes`
+    main_attributes: Vec<Vec<String>>,
+    attributes: Vec<Vec<String>>
 }
 
 impl LexiconWriter for HTMLLexiconWriter {
-    fn initialize(_: &'static str, orthographies: Vec<&'static str>) -> Self {
-        Self { orthographies }
+    fn initialize(_: Vec<&'static str>, orthographies: Vec<&'static str>, main_scripts: Vec<Option<&Script>>, scripts: Vec<Option<&Script>>) -> Self {
+        let attributes = |list: Vec<Option<&Script>>| list.into_iter().map(|script| script.map(Script::html_attributes).unwrap_or_default()).collect();
+        Self { orthographies,
+               main_attributes: attributes(main_scripts),
+               attributes: attributes(scripts) }
     }
 
-    fn write_entry(&mut self, main_spelling: &str, other_spellings: &[&str], word: &Word, definition: &str, output: &mut String) {
+    fn write_entry(&mut self, main_spellings: &[&str], other_spellings: &[&str], word: &Word, phonetic: Option<&str>, definition: &str, output: &mut String) {
         let mut buffer = html_builder::Buffer::new();
         let mut p = buffer.p();
-        write!(p.strong(), "{main_spelling}").expect("Could not write to HTML");
+        for (i, spelling) in main_spellings.iter().enumerate() {
+            if i > 0 {
+                write!(p, ", ").expect("Could not write to HTML");
+            }
+            let mut strong = self.main_attributes.get(i).into_iter().flatten().fold(p.strong(), |strong, attribute| strong.attr(attribute));
+            write!(strong, "{spelling}").expect("Could not write to HTML");
+        }
         write!(p, ". ({word}").expect("Could not write to HTML");
-        for (orthography, spelling) in self.orthographies.iter().zip(other_spellings) {
+        if let Some(phonetic) = phonetic {
+            write!(p, " {phonetic}").expect("Could not write to HTML");
+        }
+        for ((orthography, spelling), attributes) in self.orthographies.iter().zip(other_spellings).zip(&self.attributes) {
             write!(p, "; {orthography}: ").expect("Could not write to HTML");
-            write!(p.em(), "{spelling}").expect("Could not write to HTML");
+            let mut em = attributes.iter().fold(p.em(), |em, attribute| em.attr(attribute));
+            write!(em, "{spelling}").expect("Could not write to HTML");
         }
         write!(p, "): {definition}").expect("Could not write orthography");
         write!(output, "{}", buffer.finish()).expect("Could not write to HTML");
@@ -177,16 +232,21 @@ impl LexiconWriter for HTMLLexiconWriter {
 }
 
 pub(crate) struct LexiconList {
-    primary_orthography: usize,
+    primary_orthographies: Vec<usize>,
     orthographies: Vec<&'static str>,
+    scripts: Vec<Option<Script>>,
+    phonetic: bool,
     entries: Vec<LexiconEntry>
 }
 
 impl LexiconList {
-    pub(crate) const fn new(orthographies: Vec<&'static str>, primary_orthography: usize) -> Self {
-        Self { primary_orthography,
-               orthographies,
-               entries: Vec::new() }
+    pub(crate) fn new(orthographies: Vec<&'static str>, scripts: Vec<Option<Script>>, mut primary_orthographies: Vec<usize>, phonetic: bool) -> Result<Self, ElbieError> {
+        check_primary_orthographies(&orthographies, &mut primary_orthographies)?;
+        Ok(Self { primary_orthographies,
+                  orthographies,
+                  scripts,
+                  phonetic,
+                  entries: Vec::new() })
     }
 
     pub(crate) fn push_entry(&mut self, entry: LexiconEntry) {
@@ -197,37 +257,22 @@ impl LexiconList {
     }
 
     fn into_string<Writer: LexiconWriter>(self, result: &mut String) {
-        let mut main_orthography = "";
-        let mut other_orthographies = Vec::new();
-
-        for (i, orthography) in self.orthographies.iter().enumerate() {
-            if i == self.primary_orthography {
-                main_orthography = orthography
-            } else {
-                other_orthographies.push(*orthography);
-            }
-        }
-        let mut writer = Writer::initialize(main_orthography, other_orthographies);
+        let (main_orthographies, other_orthographies) = split_primary(&self.orthographies, &self.primary_orthographies);
+        let scripts = self.scripts.iter().map(Option::as_ref).collect::<Vec<_>>();
+        let (main_scripts, other_scripts) = split_primary(&scripts, &self.primary_orthographies);
+        let mut writer = Writer::initialize(main_orthographies, other_orthographies, main_scripts, other_scripts);
 
         for entry in self.entries {
-            let mut main_spelling = "";
-            let mut other_spellings = Vec::new();
-            for (i, spelling) in entry.spelling.iter().enumerate() {
-                if i == self.primary_orthography {
-                    main_spelling = spelling;
-                } else {
-                    other_spellings.push(spelling.as_str())
-                }
-            }
-
-            assert_ne!(main_spelling.len(), 0, "Missing spelling for orthography {} in {}", self.primary_orthography, entry.word);
+            let spellings = entry.spelling.iter().map(String::as_str).collect::<Vec<_>>();
+            let (main_spellings, other_spellings) = split_primary(&spellings, &self.primary_orthographies);
 
-            writer.write_entry(main_spelling, &other_spellings, &entry.word, &entry.definition, result);
+            writer.write_entry(&main_spellings, &other_spellings, &entry.word, entry.phonetic.as_deref(), &entry.definition, result);
         }
     }
 
     fn into_table(self, style: &Format) -> TableOutput {
-        let mut table = LexiconTable::new(&self.orthographies, self.primary_orthography);
+        let mut table =
+            LexiconTable::new(&self.orthographies, self.scripts, self.primary_orthographies, self.phonetic).expect("Primary orthographies should have been checked when the list was created.");
 
         for entry in self.entries {
             table.push_entry(entry);
@@ -252,6 +297,17 @@ impl LexiconList {
             },
             Format::HTML { .. } => {
                 let mut result = String::new();
+                let mut styles = Vec::new();
+                for css in self.scripts.iter().flatten().filter_map(Script::font_face_css) {
+                    if !styles.contains(&css) {
+                        styles.push(css);
+                    }
+                }
+                if !styles.is_empty() {
+                    let mut buffer = html_builder::Buffer::new();
+                    write!(buffer.style().raw(), "{}", styles.join("\n")).expect("Could not write to HTML");
+                    write!(result, "{}", buffer.finish()).expect("Could not write to HTML");
+                }
                 self.into_string::<HTMLLexiconWriter>(&mut result);
                 write!(output, "{result}")
             },
@@ -266,11 +322,11 @@ pub(crate) enum Lexicon {
 }
 
 impl Lexicon {
-    pub(crate) fn new(style: &LexiconStyle, orthographies: Vec<&'static str>, primary_orthography_idx: usize) -> Self {
-        match style {
-            LexiconStyle::Table => Self::Table(LexiconTable::new(&orthographies, primary_orthography_idx)),
-            LexiconStyle::List => Self::List(LexiconList::new(orthographies, primary_orthography_idx))
-        }
+    pub(crate) fn new(style: &LexiconStyle, orthographies: Vec<&'static str>, scripts: Vec<Option<Script>>, primary_orthographies: Vec<usize>, phonetic: bool) -> Result<Self, ElbieError> {
+        Ok(match style {
+            LexiconStyle::Table => Self::Table(LexiconTable::new(&orthographies, scripts, primary_orthographies, phonetic)?),
+            LexiconStyle::List => Self::List(LexiconList::new(orthographies, scripts, primary_orthographies, phonetic)?)
+        })
     }
 
     pub(crate) fn push_entry(&mut self, entry: LexiconEntry) {
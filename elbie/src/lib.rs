@@ -6,15 +6,13 @@ Elbie = LB, Language Builder, and is a bunch of tools for building a constructed
 FUTURE: Implementing syllable breaks, stress, etc, Simple Solution:
 - a "word" is sequence of syllables, not phonemes. A syllable is a sequence of phonemes. I don't think we need to support onset/rhyme structure, since that could be analyzed differently. In fact, some languages might not be able to analyze syllables, in which case each word would have to be one big syllable.
 - A syllable can also have stress, tone, etc.
-- spelling callbacks are the hardest part to deal with, but I'm not sure these are great anyway. Spelling might be a type of transformation.
+- spelling callbacks are the hardest part to deal with, but I'm not sure these are great anyway. Transformed orthographies (see `Language::add_transformed_orthography`) might be the answer.
 - Another difficulty is "converting" old words, which won't have the syllable breaks and stress indicators. The best thing I can think of is to have the validators guess when a syllable break is missing, and warn about modifiers missing without stopping the process.
 
 There have been arguments against syllables being a real thing, but I feel like their usage in analysis is big enough that I can still use them.
 https://web.archive.org/web/20150923211920/http://www.cunyphonologyforum.net/syllable.php
 https://web.archive.org/web/20150918220252/http://cunyphonologyforum.wikifoundry.com/page/Paraphonological+Phenomena
 
-FUTURE: Implement transformations for orthography: I would just have to make a mark that indicates the transformation is not a word and shouldn't be shown in phonemic form.
-
 FUTURE: Is there some way to use types or something else to make languages easier to create?
 - One issue is the use of string constants to identify environments, sets, phonemes, etc.
     - There is a small possibility that I could repeat the string name under two different constant names, which could cause some hard to debug issues.
@@ -156,18 +154,26 @@ pub mod phoneme;
 mod orthography;
 mod phoneme_behavior;
 pub mod word;
+pub mod text;
+pub mod script;
+mod respelling;
 mod enumerate_with_count;
 pub mod phonotactics;
 pub mod generation;
 pub mod validation;
+mod enumeration;
 pub mod phoneme_table_builder;
 pub mod language;
 pub mod transformation;
+mod sound_change;
 mod analysis;
+mod adaptation;
+mod correspondence;
 mod cli_functions;
 #[deprecated(since = "0.2.2", note = "Use `cli::run_language` instead.")]
 pub mod language_cli;
 pub mod family;
+mod family_tree;
 pub mod cli;
 #[cfg(test)]
 mod test;
@@ -1,3 +1,4 @@
+use core::cmp::Ordering;
 use core::fmt;
 use core::fmt::Display;
 use core::fmt::Formatter;
@@ -8,6 +9,8 @@ use std::rc::Rc;
 
 use crate::bag::Bag;
 use crate::errors::ElbieError;
+use crate::word::Word;
+use unicode_normalization::UnicodeNormalization as _;
 use unicode_normalization::is_nfd;
 
 pub mod ipa;
@@ -52,10 +55,23 @@ pub trait InventoryLoader {
     fn add_exclusion(&mut self, name: &'static str, set: &'static str, exclude_phoneme_strs: &[&'static str]) -> Result<(), ElbieError>;
 }
 
+fn sort_phonemes_by_length_descending(a: &Rc<Phoneme>, b: &Rc<Phoneme>) -> Ordering {
+    let name_a = a.name;
+    let len_a = name_a.len();
+    let name_b = b.name;
+    let len_b = name_b.len();
+    if len_a == len_b {
+        name_a.partial_cmp(name_b).expect("Can't order phoneme names for some reason.")
+    } else {
+        len_b.partial_cmp(&len_a).expect("Can't order phoneme lengths for some reason.")
+    }
+}
+
 #[derive(Debug)]
 pub struct Inventory {
     phonemes: HashMap<&'static str, Rc<Phoneme>>,
     sets: HashMap<&'static str, Bag<Rc<Phoneme>>>, // It seems like a hashset would be better, but I can't pick randomly from it without converting to vec anyway.
+    features: HashMap<&'static str, Vec<&'static str>>, // the sets a phoneme was added with, which doesn't include sets built from other sets.
     normalize_phonemes: bool                       // see Language::set_normalize_phonemes
 }
 
@@ -65,8 +81,10 @@ impl Default for Inventory {
         _ = sets.insert(PHONEME, Bag::new());
         _ = sets.insert(EMPTY, Bag::new());
         let phonemes = HashMap::new();
+        let features = HashMap::new();
         Self { phonemes,
                sets,
+               features,
                normalize_phonemes: false }
     }
 }
@@ -128,6 +146,19 @@ impl Inventory {
         Ok(set)
     }
 
+    /// Returns the names of every set the phoneme is in, sorted, except for the set of all phonemes.
+    pub(crate) fn sets_containing(&self, phoneme: &Rc<Phoneme>) -> Vec<&'static str> {
+        let mut result = Vec::new();
+        #[expect(clippy::iter_over_hash_type, reason = "The result is sorted")]
+        for (name, bag) in &self.sets {
+            if *name != PHONEME && bag.contains(phoneme) {
+                result.push(*name);
+            }
+        }
+        result.sort_unstable();
+        result
+    }
+
     pub(crate) fn phoneme_is(&self, phoneme: &Rc<Phoneme>, set: &'static str) -> Result<bool, ElbieError> {
         Ok(self.get_set(set)?.contains(phoneme))
     }
@@ -170,9 +201,75 @@ impl Inventory {
             self.add_phoneme_to_set(containing_set, phoneme)?;
         }
 
+        #[expect(clippy::iter_over_hash_type, reason = "Order for this doesn't matter")]
+        for (name, features) in &other.features {
+            _ = self.features.entry(name).or_insert_with(|| features.clone());
+        }
+
         Ok(())
     }
 
+    /// Finds the phoneme which was added with the same sets as the specified phoneme, except that the `remove` sets are replaced with the `add` sets. This only looks at the sets the phonemes were added with, not sets built from those. If there isn't exactly one phoneme that matches, an error is returned.
+    pub(crate) fn find_with_changed_features(&self, phoneme: &Rc<Phoneme>, remove: &[&'static str], add: &[&'static str]) -> Result<Rc<Phoneme>, ElbieError> {
+        let no_match = || ElbieError::NoPhonemeWithChangedFeatures(phoneme.name, remove.join(", "), add.join(", "));
+
+        let mut wanted: Vec<&'static str> = self.features.get(phoneme.name).ok_or_else(no_match)?.iter().copied().filter(|set| !remove.contains(set)).collect();
+        for set in add {
+            if !wanted.contains(set) {
+                wanted.push(set);
+            }
+        }
+
+        let mut found = self.features.iter().filter(|(_, features)| features.len() == wanted.len() && features.iter().all(|set| wanted.contains(set))).map(|(name, _)| name);
+        match (found.next(), found.next()) {
+            (Some(name), None) => self.get_phoneme(name).cloned(),
+            _ => Err(no_match())
+        }
+    }
+
+    /// Looks up a phoneme or set name, returning the static version of the name if it exists.
+    pub(crate) fn find_name(&self, name: &str) -> Option<&'static str> {
+        self.phonemes.get_key_value(name).map(|(key, _)| *key).or_else(|| self.sets.get_key_value(name).map(|(key, _)| *key))
+    }
+
+    pub(crate) fn read_word(&self, input: &str) -> Result<Word, ElbieError> {
+        // not an efficient algorithm, but it works...
+        // FUTURE: This should be "cached" somehow to speed up the process. Perhaps by using a BTreeMap instead of a HashMap, and forcing insertion in order when adding phonemes to it.
+        let mut phonemes: Vec<Rc<Phoneme>> = self.phonemes.values().cloned().collect();
+        // sort the phonemes so that longer phonemes come first. This should avoid longer graphemes from matching the shorter graphemes accidentally. For example, say there were phonemes "aw" and "a". If "aw" is sorted first for the match, then if it's found in the word it won't be mistaken for an "a" followed by a "w".
+        phonemes.sort_by(sort_phonemes_by_length_descending);
+
+        let mut word: Vec<Rc<Phoneme>> = vec![];
+
+        let mut source = if self.normalize_phonemes() {
+            &input.nfd().collect::<String>()
+        } else {
+            input
+        };
+
+        'outer: while !source.is_empty() {
+            for phoneme in &phonemes {
+                let name = phoneme.name;
+                if let Some(after) = source.strip_prefix(name) {
+                    word.push((*phoneme).clone()); // clone twice because apparently phoneme is a double reference
+                    source = after;
+                    continue 'outer;
+                }
+            }
+            return Err(ElbieError::UnknownPhonemeWhileReading(input.to_owned(), source.to_owned()));
+        }
+
+        Ok(Word::new(&word))
+    }
+
+    /// All phoneme and set names, longest first.
+    pub(crate) fn names_by_length(&self) -> Vec<&'static str> {
+        let mut result: Vec<&'static str> = self.phonemes.keys().chain(self.sets.keys()).copied().collect();
+        result.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        result.dedup();
+        result
+    }
+
     pub(crate) fn has_phoneme(&self, phoneme: &Rc<Phoneme>) -> bool {
         self.phonemes.get(phoneme.name).is_some_and(|p| p == phoneme)
     }
@@ -192,6 +289,7 @@ impl InventoryLoader for Inventory {
             }
 
             _ = self.phonemes.insert(phoneme.name, phoneme.clone());
+            _ = self.features.insert(phoneme.name, sets.to_vec());
             self.add_phoneme_to_set(PHONEME, phoneme.clone())?;
             for class in sets {
                 self.add_phoneme_to_set(class, phoneme.clone())?
@@ -0,0 +1,349 @@
+use crate::phoneme::Inventory;
+use crate::phoneme::Phoneme;
+use crate::phoneme::ipa;
+use crate::phoneme::ipa::consonants;
+use crate::phoneme::ipa::diacritics;
+use crate::phoneme::ipa::non_pulmonics;
+use crate::phoneme::ipa::vowels;
+use std::rc::Rc;
+use unicode_normalization::UnicodeNormalization as _;
+use unicode_normalization::char::is_combining_mark;
+
+/* NOTE:
+
+Respellings are built from the IPA symbols in the phoneme name, so a language gets one without having to do anything except use IPA for its phonemes. The phoneme is broken up into segments, each of which is a base symbol with any combining diacritics attached to it. Each segment is respelled from the table below, and spacing modifiers like 'ʰ' add their own letters. A few common affricates and diphthongs are recognized as a whole, so /t͡ʃ/ becomes "ch" instead of "tsh".
+
+Diacritics which don't have a respelling are still mentioned in the description of the phoneme, which comes from the IPA sets that the phoneme was added to.
+
+This is meant to be an aid for readers who don't know IPA, and not a real orthography. Nothing stops two phonemes from getting the same respelling, or a sequence of phonemes from looking like a different one (/s/ followed by /h/ is "sh").
+
+*/
+
+const NO_EQUIVALENT: &str = "no close English equivalent";
+
+struct Sound {
+    ipa: &'static str,
+    respelling: &'static str,
+    // an empty example means there's nothing close in English
+    example: &'static str
+}
+
+const fn sound(ipa: &'static str, respelling: &'static str, example: &'static str) -> Sound {
+    Sound { ipa,
+            respelling,
+            example }
+}
+
+const SOUNDS: &[Sound] = &[// plosives
+                           sound(consonants::P, "p", "as in pin"),
+                           sound(consonants::B, "b", "as in bin"),
+                           sound(consonants::T, "t", "as in tin"),
+                           sound(consonants::D, "d", "as in din"),
+                           sound(consonants::RIGHT_TAIL_T, "t", "like t, with the tongue curled back"),
+                           sound(consonants::RIGHT_TAIL_D, "d", "like d, with the tongue curled back"),
+                           sound(consonants::C, "ky", "like the k in cute"),
+                           sound(consonants::BARRED_DOTLESS_J, "gy", "like the g in argue"),
+                           sound(consonants::K, "k", "as in kin"),
+                           sound(consonants::G, "g", "as in go"),
+                           sound(consonants::Q, "q", "like k, further back in the throat"),
+                           sound(consonants::SMALL_CAP_G, "g", "like g, further back in the throat"),
+                           sound(consonants::GLOTTAL_STOP, "'", "the catch in uh-oh"),
+                           // nasals
+                           sound(consonants::M, "m", "as in man"),
+                           sound(consonants::LEFT_TAIL_M_AT_RIGHT, "m", "the m in comfort"),
+                           sound(consonants::N, "n", "as in no"),
+                           sound(consonants::RIGHT_TAIL_N, "n", "like n, with the tongue curled back"),
+                           sound(consonants::LEFT_TAIL_N_AT_LEFT, "ny", "the ny in canyon"),
+                           sound(consonants::ENG, "ng", "the ng in sing"),
+                           sound(consonants::SMALL_CAP_N, "ng", "like the ng in sing, further back in the throat"),
+                           // trills and taps
+                           sound(consonants::SMALL_CAP_B, "brr", "a trill of the lips"),
+                           sound(consonants::R, "rr", "a rolled r"),
+                           sound(consonants::SMALL_CAP_R, "rr", "a rolled r in the back of the throat"),
+                           sound(consonants::RIGHT_HOOK_V, "v", ""),
+                           sound(consonants::FISH_HOOK_R, "r", "the tt in American English butter"),
+                           sound(consonants::RIGHT_TAIL_R, "r", "like a quick d, with the tongue curled back"),
+                           // fricatives
+                           sound(consonants::PHI, "f", "like f, blowing through both lips"),
+                           sound(consonants::BETA, "v", "like v, with both lips"),
+                           sound(consonants::F, "f", "as in fan"),
+                           sound(consonants::V, "v", "as in van"),
+                           sound(consonants::THETA, "th", "the th in thin"),
+                           sound(consonants::ETH, "dh", "the th in this"),
+                           sound(consonants::S, "s", "as in sun"),
+                           sound(consonants::Z, "z", "as in zoo"),
+                           sound(consonants::ESH, "sh", "the sh in ship"),
+                           sound(consonants::EZH, "zh", "the s in measure"),
+                           sound(consonants::RIGHT_TAIL_S_AT_LEFT, "sh", "like sh, with the tongue curled back"),
+                           sound(consonants::RIGHT_TAIL_Z, "zh", "like the s in measure, with the tongue curled back"),
+                           sound(consonants::C_CEDILLA, "hy", "the h in hue"),
+                           sound(consonants::CURLY_TAIL_J, "y", "a buzzing y"),
+                           sound(consonants::X, "kh", "the ch in Scottish loch"),
+                           sound(consonants::GAMMA, "gh", "like the ch in Scottish loch, but voiced"),
+                           sound(consonants::CHI, "kh", "like the ch in Scottish loch, further back in the throat"),
+                           sound(consonants::INV_SMALL_CAP_R, "gh", "the r in French rouge"),
+                           sound(consonants::BARRED_H, "h", "a strong h from deep in the throat"),
+                           sound(consonants::REV_GLOTTAL_STOP, "h", "a voiced h from deep in the throat"),
+                           sound(consonants::H, "h", "as in hat"),
+                           sound(consonants::HOOKTOP_H, "h", "the h in ahead"),
+                           sound(consonants::BELTED_L, "hl", "the ll in Welsh Llanelli"),
+                           sound(consonants::L_EZH_LIGATURE, "zl", ""),
+                           // approximants
+                           sound(consonants::CURSIVE_V, "v", "a v without friction"),
+                           sound(consonants::TURNED_R, "r", "as in red"),
+                           sound(consonants::TURNED_R_RIGHT_TAIL, "r", "like r, with the tongue curled back"),
+                           sound(consonants::J, "y", "the y in yes"),
+                           sound(consonants::TURNED_M_RIGHT_LEG, "w", "like w, without rounding the lips"),
+                           sound(consonants::L, "l", "as in let"),
+                           sound(consonants::RIGHT_TAIL_L, "l", "like l, with the tongue curled back"),
+                           sound(consonants::TURNED_Y, "ly", "the lli in million"),
+                           sound(consonants::SMALL_CAP_L, "l", "like l, further back in the mouth"),
+                           sound(consonants::TURNED_W, "wh", "the wh in which, in accents that don't pronounce it as w"),
+                           sound(consonants::CURLY_TAIL_C, "sh", "like sh, with the tongue flatter"),
+                           sound(consonants::CURLY_TAIL_Z, "zh", "like the s in measure, with the tongue flatter"),
+                           sound(consonants::W, "w", "as in wet"),
+                           sound(consonants::TURNED_LONG_LEG_R, "l", "a quick l"),
+                           sound(consonants::TURNED_H, "wy", "like y, with rounded lips"),
+                           sound(consonants::HOOKTOP_HENG, "sh", ""),
+                           sound(consonants::SMALL_CAP_H, "h", ""),
+                           sound(consonants::BARRED_REV_GLOTTAL_STOP, "h", ""),
+                           sound(consonants::BARRED_GLOTTAL_STOP, "'", ""),
+                           // clicks and implosives
+                           sound(non_pulmonics::BULLS_EYE, "!", "a kissing click"),
+                           sound(non_pulmonics::PIPE, "!", "the tsk in tsk-tsk"),
+                           sound(non_pulmonics::EXCLAMATION_POINT, "!", "a popping click, like a cork"),
+                           sound(non_pulmonics::DOUBLE_BARRED_PIPE, "!", "a sharp click of the tongue"),
+                           sound(non_pulmonics::DOUBLE_PIPE, "!", "the click used to urge on a horse"),
+                           sound(non_pulmonics::HOOKTOP_B, "b", "like b, swallowing air"),
+                           sound(non_pulmonics::HOOKTOP_D, "d", "like d, swallowing air"),
+                           sound(non_pulmonics::HOOKTOP_BARRED_DOTLESS_J, "gy", ""),
+                           sound(non_pulmonics::HOOKTOP_G, "g", "like g, swallowing air"),
+                           sound(non_pulmonics::HOOKTOP_SMALL_CAP_G, "g", ""),
+                           // vowels
+                           sound(vowels::I, "ee", "the ee in see"),
+                           sound(vowels::Y, "ue", "like ee, with rounded lips"),
+                           sound(vowels::BARRED_I, "ih", ""),
+                           sound(vowels::BARRED_U, "ue", "like oo, further forward"),
+                           sound(vowels::TURNED_M, "oo", "like oo, without rounding the lips"),
+                           sound(vowels::U, "oo", "the oo in food"),
+                           sound(vowels::SMALL_CAP_I, "ih", "the i in sit"),
+                           sound(vowels::SMALL_CAP_Y, "ue", ""),
+                           sound(vowels::UPSILON, "uu", "the oo in book"),
+                           sound(vowels::E, "ay", "like the a in say, without the glide"),
+                           sound(vowels::SLASHED_O, "eu", "like ay, with rounded lips"),
+                           sound(vowels::REV_E, "uh", ""),
+                           sound(vowels::BARRED_O, "uh", ""),
+                           sound(vowels::RAMS_HORNS, "uh", ""),
+                           sound(vowels::O, "oh", "like the o in go, without the glide"),
+                           sound(vowels::SCHWA, "uh", "the a in about"),
+                           sound(vowels::EPSILON, "eh", "the e in bed"),
+                           sound(vowels::O_E_LIGATURE, "eu", "like eh, with rounded lips"),
+                           sound(vowels::REV_EPSILON, "ur", "the ir in bird, in accents that drop the r"),
+                           sound(vowels::CLOSED_REV_EPSILON, "uh", ""),
+                           sound(vowels::TURNED_V, "uh", "the u in cup"),
+                           sound(vowels::OPEN_O, "aw", "the aw in law"),
+                           sound(vowels::ASH, "a", "the a in cat"),
+                           sound(vowels::TURNED_A, "uh", "the u in cup, in some accents"),
+                           sound(vowels::A, "ah", "like the a in father, further forward"),
+                           sound(vowels::SMALL_CAP_O_E_LIGATURE, "ah", ""),
+                           sound(vowels::SCRIPT_A, "ah", "the a in father"),
+                           sound(vowels::TURNED_SCRIPT_A, "o", "the o in British English lot")];
+
+// These are matched against the base symbols of the phoneme, ignoring diacritics.
+const COMBINATIONS: &[Sound] = &[sound(constcat::concat!(consonants::T, consonants::ESH), "ch", "the ch in church"),
+                                 sound(constcat::concat!(consonants::D, consonants::EZH), "j", "the j in judge"),
+                                 sound(constcat::concat!(consonants::T, consonants::S), "ts", "the ts in cats"),
+                                 sound(constcat::concat!(consonants::D, consonants::Z), "dz", "the ds in adds"),
+                                 sound(constcat::concat!(vowels::A, vowels::I), "eye", "as in eye"),
+                                 sound(constcat::concat!(vowels::A, vowels::SMALL_CAP_I), "eye", "as in eye"),
+                                 sound(constcat::concat!(vowels::A, vowels::U), "ow", "the ow in cow"),
+                                 sound(constcat::concat!(vowels::A, vowels::UPSILON), "ow", "the ow in cow"),
+                                 sound(constcat::concat!(vowels::OPEN_O, vowels::I), "oy", "the oy in boy"),
+                                 sound(constcat::concat!(vowels::OPEN_O, vowels::SMALL_CAP_I), "oy", "the oy in boy"),
+                                 sound(constcat::concat!(vowels::TURNED_SCRIPT_A, vowels::I), "oy", "the oy in boy"),
+                                 sound(constcat::concat!(vowels::E, vowels::I), "ay", "the ay in day"),
+                                 sound(constcat::concat!(vowels::E, vowels::SMALL_CAP_I), "ay", "the ay in day"),
+                                 sound(constcat::concat!(vowels::O, vowels::U), "oh", "the o in go"),
+                                 sound(constcat::concat!(vowels::O, vowels::UPSILON), "oh", "the o in go")];
+
+struct Modifier {
+    ipa: &'static str,
+    respelling: &'static str,
+    description: &'static str
+}
+
+const fn modifier(ipa: &'static str, respelling: &'static str, description: &'static str) -> Modifier {
+    Modifier { ipa,
+               respelling,
+               description }
+}
+
+// Spacing modifiers are respelled as their own segments, combining diacritics only show up in the description.
+const MODIFIERS: &[Modifier] = &[modifier(diacritics::SUP_H, "h", "aspirated"),
+                                 modifier(diacritics::SUP_W, "w", "labialized"),
+                                 modifier(diacritics::SUP_J, "y", "palatalized"),
+                                 modifier(diacritics::SUP_GAMMA, "", "velarized"),
+                                 modifier(diacritics::SUP_REV_GLOTTAL_STOP, "", "pharyngealized"),
+                                 modifier(diacritics::SUP_N, "n", "nasally released"),
+                                 modifier(diacritics::SUP_L, "l", "laterally released"),
+                                 modifier(diacritics::RIGHT_HOOK, "r", "r-colored"),
+                                 modifier(non_pulmonics::APOSTROPHE, "'", "ejective"),
+                                 modifier(ipa::suprasegmentals::LENGTH_MARK, "", "long"),
+                                 modifier(ipa::suprasegmentals::HALF_LENGTH_MARK, "", "half-long"),
+                                 modifier(diacritics::UNDER_RING, "", ipa::UNVOICED),
+                                 modifier(diacritics::OVER_RING, "", ipa::UNVOICED),
+                                 modifier(diacritics::SUB_WEDGE, "", ipa::VOICED),
+                                 modifier(diacritics::SUB_UMLAUT, "", "breathy"),
+                                 modifier(diacritics::SUB_TILDE, "", "creaky"),
+                                 modifier(diacritics::SUP_TILDE, "", "nasalized"),
+                                 modifier(diacritics::SUB_BRIDGE, "", ipa::DENTAL),
+                                 modifier(diacritics::UNDER_BAR, "", "retracted"),
+                                 modifier(diacritics::SUB_PLUS, "", "advanced"),
+                                 modifier(diacritics::SYLLABICITY_MARK, "", "syllabic"),
+                                 modifier(diacritics::SYLLABICITY_MARK_ABOVE, "", "syllabic"),
+                                 modifier(ipa::suprasegmentals::BREVE, "", "extra-short")];
+
+// The order of these is the order the words are used in the description.
+const VOWEL_FEATURES: &[&[&str]] = &[&[ipa::CLOSE, ipa::NEAR_CLOSE, ipa::CLOSE_MID, ipa::MID, ipa::OPEN_MID, ipa::NEAR_OPEN, ipa::OPEN],
+                                     &[ipa::FRONT, ipa::NEAR_FRONT, ipa::CENTRAL, ipa::NEAR_BACK, ipa::BACK],
+                                     &[ipa::ROUNDED, ipa::UNROUNDED]];
+
+const CONSONANT_FEATURES: &[&[&str]] =
+    &[&[ipa::VOICED, ipa::UNVOICED],
+      &[ipa::BILABIAL, ipa::LABIODENTAL, ipa::DENTAL, ipa::ALVEOLAR, ipa::POSTALVEOLAR, ipa::RETROFLEX, ipa::PALATAL, ipa::VELAR, ipa::UVULAR, ipa::PHARYNGEAL, ipa::GLOTTAL],
+      &[ipa::EJECTIVE, ipa::VOICED_IMPLOSIVE, ipa::CLICK]];
+
+const MANNERS: &[&str] = &[ipa::PLOSIVE, ipa::NASAL, ipa::TRILL, ipa::TAP_OR_FLAP, ipa::FRICATIVE, ipa::LATERAL_FRICATIVE, ipa::APPROXIMANT, ipa::LATERAL_APPROXIMANT];
+
+fn is_tie_bar(c: char) -> bool {
+    diacritics::BOTTOM_TIE_BAR.starts_with(c) || diacritics::TOP_TIE_BAR.starts_with(c)
+}
+
+// Splits the phoneme into base symbols with their combining diacritics. Tie bars are dropped.
+fn split_segments(phoneme: &str) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for c in phoneme.nfd() {
+        if is_tie_bar(c) {
+            continue;
+        }
+        if is_combining_mark(c)
+           && let Some(segment) = result.last_mut()
+        {
+            segment.push(c);
+            continue;
+        }
+        result.push(c.to_string());
+    }
+    result
+}
+
+fn same_text(a: &str, b: &str) -> bool {
+    a.nfd().eq(b.nfd())
+}
+
+fn find_modifier(segment: &str) -> Option<&'static Modifier> {
+    MODIFIERS.iter().find(|modifier| same_text(modifier.ipa, segment))
+}
+
+fn find_sound(segment: &str) -> Option<&'static Sound> {
+    if let Some(sound) = SOUNDS.iter().find(|sound| same_text(sound.ipa, segment)) {
+        return Some(sound);
+    }
+    // try again without the diacritics
+    let base = segment.chars().next()?;
+    SOUNDS.iter().find(|sound| same_text(sound.ipa, base.encode_utf8(&mut [0; 4])))
+}
+
+fn find_combination(segments: &[String]) -> Option<&'static Sound> {
+    let bases: String = segments.iter().filter(|segment| find_modifier(segment).is_none()).filter_map(|segment| segment.chars().next()).collect();
+    COMBINATIONS.iter().find(|combination| same_text(combination.ipa, &bases))
+}
+
+/// Respells a phoneme with English-friendly letters, based on the IPA symbols in its name.
+pub(crate) fn respell(phoneme: &str) -> String {
+    let segments = split_segments(phoneme);
+    let combination = find_combination(&segments);
+    let mut result = String::new();
+    if let Some(combination) = combination {
+        result.push_str(combination.respelling);
+    }
+    for segment in &segments {
+        if let Some(modifier) = find_modifier(segment) {
+            result.push_str(modifier.respelling);
+        } else if combination.is_none() {
+            match find_sound(segment) {
+                Some(sound) => result.push_str(sound.respelling),
+                // It's not IPA, or not something I know about, so leave it alone.
+                None => result.push_str(segment)
+            }
+        }
+    }
+    result
+}
+
+/// Describes how to pronounce the phoneme using English words, where possible.
+pub(crate) fn english_equivalent(phoneme: &str) -> String {
+    let segments = split_segments(phoneme);
+    if let Some(combination) = find_combination(&segments) {
+        return combination.example.to_owned();
+    }
+    let sounds: Vec<&Sound> = segments.iter().filter(|segment| find_modifier(segment).is_none()).filter_map(|segment| find_sound(segment)).collect();
+    match sounds.as_slice() {
+        [] => NO_EQUIVALENT.to_owned(),
+        [sound] if sound.example.is_empty() => NO_EQUIVALENT.to_owned(),
+        [sound] => sound.example.to_owned(),
+        _ => sounds.iter()
+                   .map(|sound| {
+                       let example = if sound.example.is_empty() {
+                           NO_EQUIVALENT
+                       } else {
+                           sound.example
+                       };
+                       format!("{} ({example})", sound.respelling)
+                   })
+                   .collect::<Vec<_>>()
+                   .join(", then ")
+    }
+}
+
+/// Describes the phoneme in phonetic terms, based on which of the IPA sets it belongs to, and the diacritics in its name.
+pub(crate) fn describe(inventory: &Inventory, phoneme: &Rc<Phoneme>) -> String {
+    // sets which aren't in the language just aren't part of the description.
+    let is = |set: &'static str| matches!(inventory.phoneme_is(phoneme, set), Ok(true));
+
+    let mut words: Vec<&str> = Vec::new();
+    for c in phoneme.name.nfd() {
+        if let Some(modifier) = find_modifier(c.encode_utf8(&mut [0; 4]))
+           && !words.contains(&modifier.description)
+        {
+            words.push(modifier.description);
+        }
+    }
+
+    if is(ipa::VOWEL) {
+        words.extend(VOWEL_FEATURES.iter().flat_map(|group| group.iter()).copied().filter(|set| is(set)));
+        let sound_count = split_segments(phoneme.name).iter().filter(|segment| find_modifier(segment).is_none()).count();
+        words.push(if sound_count > 1 {
+                       "diphthong"
+                   } else {
+                       ipa::VOWEL
+                   });
+    } else {
+        words.extend(CONSONANT_FEATURES.iter().flat_map(|group| group.iter()).copied().filter(|set| is(set)));
+        let manners: Vec<&str> = MANNERS.iter().copied().filter(|set| is(set)).collect();
+        if !manners.is_empty() {
+            words.extend(manners);
+        } else if is(ipa::CONSONANT) {
+            words.push(ipa::CONSONANT);
+        }
+    }
+
+    // the diacritics might say the same thing as the sets.
+    let mut seen = Vec::new();
+    words.retain(|word| {
+             let is_new = !seen.contains(word);
+             seen.push(word);
+             is_new
+         });
+    words.join(" ").replace('_', " ")
+}
@@ -0,0 +1,109 @@
+use crate::grid::Cell;
+use crate::grid::Grid;
+use core::fmt;
+use core::fmt::Display;
+use core::fmt::Formatter;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum ScriptDirection {
+    #[default]
+    LeftToRight,
+    RightToLeft
+}
+
+impl Display for ScriptDirection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        // these are the values for the HTML dir attribute
+        match self {
+            Self::LeftToRight => write!(f, "ltr"),
+            Self::RightToLeft => write!(f, "rtl")
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Glyph {
+    text: &'static str,
+    name: &'static str,
+    notes: &'static str
+}
+
+impl Glyph {
+    pub(crate) const fn text(&self) -> &'static str {
+        self.text
+    }
+
+    pub(crate) const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub(crate) const fn notes(&self) -> &'static str {
+        self.notes
+    }
+}
+
+/**
+Describes a native script used by an orthography, for when the spellings aren't in a script that can be displayed without help. Usually, the spellings for the phonemes will be characters from the Unicode Private Use Area (U+E000 to U+F8FF), and a font is needed that maps those to the actual glyphs.
+
+The font family is used in HTML output, and if a font source is given, an `@font-face` rule will be embedded to load it. Glyph metadata is only informational, and can be displayed with the spelling command.
+*/
+#[derive(Debug, Clone)]
+pub struct Script {
+    font_family: &'static str,
+    font_source: Option<&'static str>,
+    direction: ScriptDirection,
+    glyphs: Vec<Glyph>
+}
+
+impl Script {
+    #[must_use]
+    pub const fn new(font_family: &'static str) -> Self {
+        Self { font_family,
+               font_source: None,
+               direction: ScriptDirection::LeftToRight,
+               glyphs: Vec::new() }
+    }
+
+    /// A URL or path that the font can be loaded from in HTML output.
+    #[must_use]
+    pub const fn with_font_source(mut self, source: &'static str) -> Self {
+        self.font_source = Some(source);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_direction(mut self, direction: ScriptDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Adds information about a glyph. The text should be the same as what is used to spell phonemes with it.
+    #[must_use]
+    pub fn with_glyph(mut self, text: &'static str, name: &'static str, notes: &'static str) -> Self {
+        self.glyphs.push(Glyph { text,
+                                 name,
+                                 notes });
+        self
+    }
+
+    pub(crate) fn glyphs(&self) -> &[Glyph] {
+        &self.glyphs
+    }
+
+    pub(crate) fn font_face_css(&self) -> Option<String> {
+        self.font_source.map(|source| format!("@font-face {{ font-family: '{}'; src: url('{source}'); }}", self.font_family))
+    }
+
+    /// Attributes to add to HTML elements containing text in this script.
+    pub(crate) fn html_attributes(&self) -> Vec<String> {
+        vec![format!("dir=\"{}\"", self.direction), format!("style=\"font-family: '{}'\"", self.font_family)]
+    }
+
+    /// Styles a cell containing text in this script, and adds any font it needs to the grid.
+    pub(crate) fn style_cell(&self, grid: &mut Grid, cell: Cell) -> Cell {
+        if let Some(css) = self.font_face_css() {
+            grid.add_html_style(css);
+        }
+        cell.with_html_attributes(self.html_attributes())
+    }
+}
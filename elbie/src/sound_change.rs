@@ -0,0 +1,295 @@
+use crate::errors::ElbieError;
+use crate::phoneme::Inventory;
+use crate::phoneme::Phoneme;
+use crate::transformation::RuleState;
+use crate::transformation::RuleStateError;
+use std::rc::Rc;
+
+/* NOTE:
+
+Sound changes can be written in the notation used by most sound change appliers:
+
+    target > replacement / environment
+
+- The target and replacement are sequences of phonemes. Either can be '∅' (or left empty) to delete or insert phonemes.
+- The environment is optional. It contains a '_' where the target goes, and can start or end with '#' for a word boundary.
+- Phoneme and set names from the transformation's inventory can be written directly. Names are matched longest first, so `vowel_vowel` is two sets. Spaces between them are ignored.
+- `{a,b,c}` matches any of the listed phonemes or sets. A list in the replacement is paired up with a list in the target, so `{p,t,k} > {b,d,g}` replaces /p/ with /b/, and so on.
+- `[+set,-set]` matches a phoneme which is in (or not in) every listed set. The '+' is optional.
+- `[+set,-set]` in the replacement changes the features of the target phoneme in the same position, adding it to the '+' sets and removing it from the '-' sets. So `[consonant,unvoiced] > [+voiced,-unvoiced] / vowel_vowel` voices consonants between vowels. See `RuleState::repl_feature`.
+- A digit in the replacement copies the phoneme matched by that element of the target, counting from 1. So `consonant vowel > 2 1` is metathesis, `consonant > 1 1 / vowel_vowel` is gemination, and `nasal consonant > 2 2` is total assimilation. See `RuleState::repl_captured`.
+
+The parsed rule doesn't do anything you couldn't do with `RuleState`. The left environment is matched with `behind_seq`, so the match starts at the target.
+
+*/
+
+const TARGET_MARK: char = '_';
+const BOUNDARY_MARK: char = '#';
+const NOTHING_MARK: char = '∅';
+const ARROWS: &[char] = &['>', '→'];
+const ENVIRONMENT_MARK: char = '/';
+
+#[derive(Debug, Clone)]
+enum Element {
+    Name(&'static str),
+    Choice(Vec<&'static str>),
+    Features(Vec<(bool, &'static str)>),
+    Boundary,
+    Target,
+    // index into the target, only allowed in the replacement
+    Copy(usize)
+}
+
+#[derive(Debug, Clone)]
+enum Replacement {
+    Phoneme(Rc<Phoneme>),
+    // chosen by the index of the matching choice in the target
+    Choice(Vec<Rc<Phoneme>>),
+    // changes the target phoneme at the same position
+    Features(Vec<&'static str>, Vec<&'static str>),
+    // copies the target phoneme at that index
+    Copy(usize)
+}
+
+#[derive(Debug)]
+pub(crate) struct SoundChange {
+    before: Vec<Element>,
+    target: Vec<Element>,
+    after: Vec<Element>,
+    replacement: Vec<Replacement>
+}
+
+struct Parser<'notation> {
+    notation: &'notation str,
+    inventory: &'notation Inventory,
+    names: Vec<&'static str>
+}
+
+impl Parser<'_> {
+    fn error(&self, message: String) -> ElbieError {
+        ElbieError::InvalidSoundChange(self.notation.to_owned(), message)
+    }
+
+    fn resolve(&self, name: &str) -> Result<&'static str, ElbieError> {
+        self.inventory.find_name(name).ok_or_else(|| self.error(format!("unknown phoneme or set '{name}'")))
+    }
+
+    fn resolve_phoneme(&self, name: &str) -> Result<Rc<Phoneme>, ElbieError> {
+        self.inventory.get_phoneme(self.resolve(name)?).cloned().map_err(|_| self.error(format!("'{name}' is a set, but only phonemes can be used in the replacement")))
+    }
+
+    fn split_list<'text>(&self, text: &'text str, close: char) -> Result<(Vec<&'text str>, &'text str), ElbieError> {
+        let (list, rest) = text.split_once(close).ok_or_else(|| self.error(format!("missing '{close}'")))?;
+        Ok((list.split(',').map(str::trim).filter(|item| !item.is_empty()).collect(), rest))
+    }
+
+    fn parse_sequence(&self, text: &str) -> Result<Vec<Element>, ElbieError> {
+        let mut result = Vec::new();
+        let mut rest = text.trim();
+        while !rest.is_empty() {
+            if let Some(name) = self.names.iter().find(|name| rest.starts_with(**name)) {
+                result.push(Element::Name(name));
+                rest = rest.strip_prefix(*name).unwrap_or_default();
+            } else if let Some(list) = rest.strip_prefix('{') {
+                let (items, after) = self.split_list(list, '}')?;
+                let names = items.into_iter().map(|item| self.resolve(item)).collect::<Result<Vec<_>, _>>()?;
+                if names.is_empty() {
+                    return Err(self.error("empty '{}' list".to_owned()));
+                }
+                result.push(Element::Choice(names));
+                rest = after;
+            } else if let Some(list) = rest.strip_prefix('[') {
+                let (items, after) = self.split_list(list, ']')?;
+                let mut features = Vec::new();
+                for item in items {
+                    let (positive, name) = match (item.strip_prefix('+'), item.strip_prefix('-')) {
+                        (Some(name), _) => (true, name),
+                        (None, Some(name)) => (false, name),
+                        (None, None) => (true, item)
+                    };
+                    features.push((positive, self.resolve(name.trim())?));
+                }
+                result.push(Element::Features(features));
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix(BOUNDARY_MARK) {
+                result.push(Element::Boundary);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix(TARGET_MARK) {
+                result.push(Element::Target);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix(NOTHING_MARK) {
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix(char::is_whitespace) {
+                rest = after;
+            } else if let Some(digit) = rest.chars().next().and_then(|first| first.to_digit(10)) {
+                let index = (digit as usize).checked_sub(1).ok_or_else(|| self.error("copies are counted from 1".to_owned()))?;
+                result.push(Element::Copy(index));
+                rest = rest.get(1..).unwrap_or_default();
+            } else {
+                return Err(self.error(format!("unknown phoneme or set starting at '{rest}'")));
+            }
+        }
+        Ok(result)
+    }
+
+    fn parse_replacement(&self, text: &str) -> Result<Vec<Replacement>, ElbieError> {
+        self.parse_sequence(text)?
+            .into_iter()
+            .map(|element| match element {
+                Element::Name(name) => Ok(Replacement::Phoneme(self.resolve_phoneme(name)?)),
+                Element::Choice(names) => Ok(Replacement::Choice(names.into_iter().map(|name| self.resolve_phoneme(name)).collect::<Result<_, _>>()?)),
+                Element::Features(features) => {
+                    let (add, remove): (Vec<_>, Vec<_>) = features.into_iter().partition(|(positive, _)| *positive);
+                    Ok(Replacement::Features(remove.into_iter().map(|(_, set)| set).collect(), add.into_iter().map(|(_, set)| set).collect()))
+                },
+                Element::Copy(index) => Ok(Replacement::Copy(index)),
+                Element::Boundary | Element::Target => Err(self.error("the replacement can only contain phonemes, '{}' lists and '[]' features".to_owned()))
+            })
+            .collect()
+    }
+
+    fn parse(&self) -> Result<SoundChange, ElbieError> {
+        let (target, rest) = self.notation.split_once(ARROWS).ok_or_else(|| self.error("missing '>'".to_owned()))?;
+        let (replacement, environment) = rest.split_once(ENVIRONMENT_MARK).unwrap_or((rest, "_"));
+
+        let target = self.parse_sequence(target)?;
+        if target.iter().any(|element| matches!(element, Element::Boundary | Element::Target | Element::Copy(_))) {
+            return Err(self.error("the target can not contain '#', '_' or copies".to_owned()));
+        }
+
+        let replacement = self.parse_replacement(replacement)?;
+        let target_choices: Vec<usize> = target.iter()
+                                               .filter_map(|element| match element {
+                                                   Element::Choice(names) => Some(names.len()),
+                                                   _ => None
+                                               })
+                                               .collect();
+        let replacement_choices: Vec<usize> = replacement.iter()
+                                                         .filter_map(|element| match element {
+                                                             Replacement::Choice(phonemes) => Some(phonemes.len()),
+                                                             Replacement::Phoneme(_) | Replacement::Features(..) | Replacement::Copy(_) => None
+                                                         })
+                                                         .collect();
+        if !replacement_choices.is_empty() && replacement_choices != target_choices {
+            return Err(self.error("the '{}' lists in the replacement must match the lists in the target".to_owned()));
+        }
+
+        let features_out_of_range = replacement.iter().enumerate().any(|(index, element)| matches!(element, Replacement::Features(..)) && index >= target.len());
+        if features_out_of_range {
+            return Err(self.error("a '[]' feature change in the replacement needs a target phoneme in the same position".to_owned()));
+        }
+
+        let copy_out_of_range = replacement.iter().any(|element| matches!(element, Replacement::Copy(index) if *index >= target.len()));
+        if copy_out_of_range {
+            return Err(self.error(format!("the target only has {} phonemes to copy", target.len())));
+        }
+
+        let environment = self.parse_sequence(environment)?;
+        if environment.iter().any(|element| matches!(element, Element::Copy(_))) {
+            return Err(self.error("the environment can not contain copies".to_owned()));
+        }
+        let mut parts = environment.split(|element| matches!(element, Element::Target));
+        let (Some(before), Some(after), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(self.error("the environment must contain exactly one '_'".to_owned()));
+        };
+        let boundary_misplaced = before.iter().skip(1).any(|element| matches!(element, Element::Boundary)) || after.iter().rev().skip(1).any(|element| matches!(element, Element::Boundary));
+        if boundary_misplaced {
+            return Err(self.error("'#' can only be at the start or end of the environment".to_owned()));
+        }
+
+        Ok(SoundChange { before: before.to_vec(),
+                         target,
+                         after: after.to_vec(),
+                         replacement })
+    }
+}
+
+impl SoundChange {
+    pub(crate) fn parse(notation: &str, inventory: &Inventory) -> Result<Self, ElbieError> {
+        let parser = Parser { notation,
+                              inventory,
+                              names: inventory.names_by_length() };
+        parser.parse()
+    }
+
+    // Returns the index of the choice that matched, if the element was a choice.
+    fn match_element(element: &Element, rule: &mut RuleState) -> Result<Option<usize>, RuleStateError> {
+        match element {
+            Element::Name(name) => rule.is(name)?,
+            Element::Choice(names) => {
+                for (index, name) in names.iter().enumerate() {
+                    if rule.opt(name)? {
+                        return Ok(Some(index));
+                    }
+                }
+                return Err(RuleStateError::MatchFailed);
+            },
+            Element::Features(features) => {
+                let phoneme = rule.peek().cloned().ok_or(RuleStateError::MatchFailed)?;
+                for (positive, set) in features {
+                    if rule.phoneme_is(&phoneme, set)? != *positive {
+                        return Err(RuleStateError::MatchFailed);
+                    }
+                }
+                rule.any()?
+            },
+            // the boundary at the start of the environment is handled in `apply`, so this is the end.
+            Element::Boundary => rule.final_()?,
+            Element::Target | Element::Copy(_) => ()
+        }
+        Ok(None)
+    }
+
+    pub(crate) fn apply(&self, rule: &mut RuleState) -> Result<bool, RuleStateError> {
+        // every element except the boundary matches one phoneme.
+        let before_length = self.before.iter().filter(|element| !matches!(element, Element::Boundary)).count();
+        rule.behind_seq(before_length, |behind| {
+                for element in &self.before {
+                    if matches!(element, Element::Boundary) {
+                        behind.initial()?;
+                    } else {
+                        _ = Self::match_element(element, behind)?;
+                    }
+                }
+                Ok(true)
+            })?;
+
+        let start_index = rule.position();
+        let mut choices = Vec::new();
+        let mut matched = Vec::new();
+        for element in &self.target {
+            matched.extend(rule.peek().cloned());
+            if let Some(choice) = Self::match_element(element, rule)? {
+                choices.push(choice);
+            }
+        }
+        let length = rule.position() - start_index;
+
+        for element in &self.after {
+            _ = Self::match_element(element, rule)?;
+        }
+
+        let mut choices = choices.into_iter();
+        let mut replace = Vec::new();
+        for (index, element) in self.replacement.iter().enumerate() {
+            match element {
+                Replacement::Phoneme(phoneme) => replace.push(phoneme.clone()),
+                Replacement::Choice(phonemes) => {
+                    // the parser makes sure these line up.
+                    if let Some(phoneme) = choices.next().and_then(|choice| phonemes.get(choice)) {
+                        replace.push(phoneme.clone())
+                    }
+                },
+                Replacement::Features(remove, add) => {
+                    if let Some(phoneme) = matched.get(index) {
+                        replace.push(rule.find_with_changed_features(phoneme, remove, add)?);
+                    }
+                },
+                Replacement::Copy(copied) => replace.extend(matched.get(*copied).cloned())
+            }
+        }
+
+        rule.splice(start_index, length, replace);
+        Ok(true)
+    }
+}
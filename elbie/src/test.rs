@@ -26,3 +26,661 @@ fn test_bags() {
     let bag_difference2 = bag_b.difference(&bag_a);
     assert_eq!(bag_difference2.to_vec(), vec![6]);
 }
+
+fn rule_application_language() -> super::language::Language {
+    named_language("test")
+}
+
+fn named_language(name: &'static str) -> super::language::Language {
+    use crate::language::Language;
+    use crate::phoneme::InventoryLoader as _;
+    use crate::phoneme::PHONEME;
+
+    let mut language = Language::with_pattern(name, vec!["Spelling"], |pattern| {
+        pattern.set(PHONEME);
+    });
+    for phoneme in ["a", "i", "k", "t"] {
+        _ = language.add_phoneme(phoneme, &[]).expect("Phoneme should be added");
+    }
+    language
+}
+
+// transforms the word with a single rule, which replaces a with i after (or before, if `before` is false) an i.
+fn apply_spreading_rule(application: super::transformation::RuleApplication, before: bool, word: &str) -> String {
+    use crate::transformation::Transformation;
+    use crate::word::Word;
+    use std::collections::HashMap;
+
+    let language = rule_application_language();
+    let mut transformation = Transformation::from(&language);
+    transformation.add_rule("spread", move |rule| {
+                      if before {
+                          rule.behind(&["i"])?;
+                          rule.repl("a", &["i"])?;
+                      } else {
+                          rule.repl("a", &["i"])?;
+                          rule.is("i")?;
+                      }
+                      Ok(true)
+                  });
+    transformation.set_rule_application("spread", application).expect("Rule should exist");
+    let word = language.read_word(word).expect("Word should be read");
+    let result = transformation.transform(&word, &HashMap::new(), None).expect("Word should be transformed");
+    result.iter().map(Word::to_raw_string).collect::<Vec<_>>().join("~")
+}
+
+// transforms the word with a single rule, which replaces the phoneme with two copies of itself, or deletes it.
+fn apply_feeding_rule(application: super::transformation::RuleApplication, phoneme: &'static str, replace: &'static [&'static str], word: &str) -> Result<String, super::errors::ElbieError> {
+    use crate::transformation::Transformation;
+    use crate::word::Word;
+    use std::collections::HashMap;
+
+    let language = rule_application_language();
+    let mut transformation = Transformation::from(&language);
+    transformation.add_rule("feed", move |rule| {
+                      rule.repl(phoneme, replace)?;
+                      Ok(true)
+                  });
+    transformation.set_rule_application("feed", application)?;
+    let word = language.read_word(word)?;
+    let result = transformation.transform(&word, &HashMap::new(), None)?;
+    Ok(result.iter().map(Word::to_raw_string).collect::<Vec<_>>().join("~"))
+}
+
+#[test]
+fn test_rule_application_simultaneous() {
+    use crate::transformation::RuleApplication;
+
+    // only the original word is matched, so the change doesn't spread.
+    assert_eq!(apply_spreading_rule(RuleApplication::Simultaneous, true, "iaa"), "iia");
+    assert_eq!(apply_spreading_rule(RuleApplication::Simultaneous, false, "aai"), "aii");
+    assert_eq!(apply_feeding_rule(RuleApplication::Simultaneous, "k", &["k", "k"], "akak").expect("Rule should finish"), "akkakk");
+}
+
+#[test]
+fn test_rule_application_left_to_right() {
+    use crate::transformation::RuleApplication;
+
+    assert_eq!(apply_spreading_rule(RuleApplication::LeftToRight, true, "iaa"), "iii");
+    assert_eq!(apply_spreading_rule(RuleApplication::LeftToRight, false, "aai"), "aii");
+    // a rule that feeds itself must not match its own output.
+    assert_eq!(apply_feeding_rule(RuleApplication::LeftToRight, "k", &["k", "k"], "akak").expect("Rule should finish"), "akkakk");
+    assert_eq!(apply_feeding_rule(RuleApplication::LeftToRight, "a", &["a", "a"], "aka").expect("Rule should finish"), "aakaa");
+    // a deletion should still see the phoneme which moves into its place.
+    assert_eq!(apply_feeding_rule(RuleApplication::LeftToRight, "k", &[], "akka").expect("Rule should finish"), "aa");
+}
+
+#[test]
+fn test_rule_application_right_to_left() {
+    use crate::transformation::RuleApplication;
+
+    assert_eq!(apply_spreading_rule(RuleApplication::RightToLeft, true, "iaa"), "iia");
+    assert_eq!(apply_spreading_rule(RuleApplication::RightToLeft, false, "aai"), "iii");
+    assert_eq!(apply_feeding_rule(RuleApplication::RightToLeft, "k", &["k", "k"], "akak").expect("Rule should finish"), "akkakk");
+    assert_eq!(apply_feeding_rule(RuleApplication::RightToLeft, "k", &[], "akka").expect("Rule should finish"), "aa");
+}
+
+#[test]
+fn test_rule_application_iterative() {
+    use crate::transformation::RuleApplication;
+
+    assert_eq!(apply_spreading_rule(RuleApplication::Iterative, true, "iaa"), "iii");
+    assert_eq!(apply_spreading_rule(RuleApplication::Iterative, false, "aai"), "iii");
+    assert_eq!(apply_feeding_rule(RuleApplication::Iterative, "k", &[], "akka").expect("Rule should finish"), "aa");
+}
+
+#[test]
+fn test_rule_exceptions() {
+    use crate::errors::ElbieError;
+    use crate::transformation::Transformation;
+    use crate::word::Word;
+    use std::collections::HashMap;
+
+    let language = rule_application_language();
+    let mut transformation = Transformation::from(&language);
+    transformation.add_rule("fronting", |rule| {
+                      rule.repl("k", &["t"])?;
+                      Ok(true)
+                  });
+    transformation.add_rule_exceptions("fronting", &["/aki/", "ika"]).expect("Exceptions should be read");
+    assert!(matches!(transformation.add_rule_exceptions("fronting", &["abi"]), Err(ElbieError::UnknownPhonemeWhileReading(..))));
+
+    let transform = |word: &str| {
+        let word = language.read_word(word).expect("Word should be read");
+        transformation.transform(&word, &HashMap::new(), None).expect("Word should be transformed").iter().map(Word::to_raw_string).collect::<Vec<_>>().join("~")
+    };
+    assert_eq!(transform("aki"), "aki");
+    assert_eq!(transform("ika"), "ika");
+    assert_eq!(transform("akka"), "atta");
+}
+
+#[test]
+fn test_rule_probability_is_repeatable() {
+    use crate::transformation::RuleVariation;
+    use crate::transformation::Transformation;
+    use crate::word::Word;
+    use std::collections::HashMap;
+
+    let language = rule_application_language();
+    let mut transformation = Transformation::from(&language);
+    transformation.add_rule("sporadic", |rule| {
+                      rule.repl("k", &["t"])?;
+                      Ok(true)
+                  });
+    transformation.set_rule_variation("sporadic", RuleVariation::Probability(0.5)).expect("Probability should be valid");
+
+    let words = ["aka", "iki", "kaki", "akik", "kikak", "aakk"].map(|word| language.read_word(word).expect("Word should be read"));
+    let transform_all = |transformer: &Transformation| {
+        words.iter()
+             .map(|word| transformer.transform(word, &HashMap::new(), None).expect("Word should be transformed").iter().map(Word::to_raw_string).collect::<Vec<_>>().join("~"))
+             .collect::<Vec<_>>()
+    };
+    let first = transform_all(&transformation);
+    assert_eq!(transform_all(&transformation), first);
+    // the words are decided separately, so the order they're transformed in doesn't matter.
+    assert_eq!(transformation.transform(words.last().expect("Words should not be empty"), &HashMap::new(), None)
+                             .expect("Word should be transformed")
+                             .iter()
+                             .map(Word::to_raw_string)
+                             .collect::<Vec<_>>(),
+               vec![first.last().expect("Words should not be empty").clone()]);
+}
+
+#[test]
+fn test_rule_probability_is_pinned() {
+    use crate::transformation::RuleVariation;
+    use crate::transformation::Transformation;
+    use crate::word::Word;
+    use std::collections::HashMap;
+
+    let language = rule_application_language();
+    let mut transformation = Transformation::from(&language);
+    transformation.add_rule("sporadic", |rule| {
+                      rule.repl("k", &["t"])?;
+                      Ok(true)
+                  });
+    transformation.set_rule_variation("sporadic", RuleVariation::Probability(0.5)).expect("Probability should be valid");
+
+    // these only change if the hash in `Transformation::random_bool` changes, which would change every sporadic rule for every user.
+    let words = ["aka", "iki", "kaki", "akik", "kikak", "aakk"].map(|word| language.read_word(word).expect("Word should be read"));
+    let mut transform_all = |seed: u64| {
+        transformation.set_seed(seed);
+        words.iter()
+             .map(|word| transformation.transform(word, &HashMap::new(), None).expect("Word should be transformed").iter().map(Word::to_raw_string).collect::<Vec<_>>().join("~"))
+             .collect::<Vec<_>>()
+    };
+    assert_eq!(transform_all(0), ["ata", "iki", "kaki", "akik", "titat", "aatt"]);
+    assert_eq!(transform_all(1), ["aka", "iki", "tati", "atit", "kikak", "aatt"]);
+}
+
+fn sound_change_language() -> super::language::Language {
+    use crate::language::Language;
+    use crate::phoneme::InventoryLoader as _;
+    use crate::phoneme::PHONEME;
+
+    let mut language = Language::with_pattern("test", vec!["Spelling"], |pattern| {
+        pattern.set(PHONEME);
+    });
+    let phonemes: [(&'static str, &[&'static str]); 10] = [("p", &["consonant", "stop", "unvoiced"]),
+                                                           ("t", &["consonant", "stop", "unvoiced", "coronal"]),
+                                                           ("b", &["consonant", "stop", "voiced"]),
+                                                           ("d", &["consonant", "stop", "voiced", "coronal"]),
+                                                           ("m", &["consonant", "nasal", "voiced"]),
+                                                           ("n", &["consonant", "nasal", "voiced", "coronal"]),
+                                                           ("a", &["vowel", "open"]),
+                                                           ("i", &["vowel", "close"]),
+                                                           ("e", &["vowel", "open_mid", "front"]),
+                                                           ("o", &["vowel", "open_mid", "back"])];
+    for (phoneme, sets) in phonemes {
+        _ = language.add_phoneme(phoneme, sets).expect("Phoneme should be added");
+    }
+    language
+}
+
+// transforms the word with a single rule written in sound change notation.
+fn apply_sound_change(notation: &str, word: &str) -> Result<String, super::errors::ElbieError> {
+    use crate::transformation::Transformation;
+    use crate::word::Word;
+    use std::collections::HashMap;
+
+    let language = sound_change_language();
+    let mut transformation = Transformation::from(&language);
+    transformation.add_sound_change("change", notation)?;
+    let word = language.read_word(word)?;
+    let result = transformation.transform(&word, &HashMap::new(), None)?;
+    Ok(result.iter().map(Word::to_raw_string).collect::<Vec<_>>().join("~"))
+}
+
+#[test]
+fn test_sound_change_parse_errors() {
+    use crate::errors::ElbieError;
+
+    for notation in ["p b",
+                     "x > b",
+                     "{p,t > b",
+                     "{} > b",
+                     "[voiced > b",
+                     "p > consonant",
+                     "p > b _",
+                     "# p > b",
+                     "p _ > b",
+                     "1 > b",
+                     "{p,t} > {b,d,m}",
+                     "p > b [voiced]",
+                     "p > 2",
+                     "p > 0",
+                     "p > b / a",
+                     "p > b / a _ _",
+                     "p > b / 1 _",
+                     "p > b / a # _",
+                     "p > b / _ # a"]
+    {
+        assert!(matches!(apply_sound_change(notation, "pa"), Err(ElbieError::InvalidSoundChange(..))), "'{notation}' should not parse");
+    }
+}
+
+#[test]
+fn test_sound_change_elements() {
+    use crate::errors::ElbieError;
+
+    // names
+    assert_eq!(apply_sound_change("p > b", "papa").expect("Rule should apply"), "baba");
+    assert_eq!(apply_sound_change("p a > m", "papi").expect("Rule should apply"), "mpi");
+    // sets
+    assert_eq!(apply_sound_change("nasal > n / _ stop", "ampa").expect("Rule should apply"), "anpa");
+    // choices, with the replacement paired up with the target
+    assert_eq!(apply_sound_change("{p,t} > {b,d}", "pat").expect("Rule should apply"), "bad");
+    assert_eq!(apply_sound_change("{stop,nasal} > i / a _", "amin").expect("Rule should apply"), "aiin");
+    assert_eq!(apply_sound_change("a > e / {m,coronal} _", "pamata").expect("Rule should apply"), "pamete");
+    // features, in the target and the replacement
+    assert_eq!(apply_sound_change("[stop,-coronal] > m", "pbtd").expect("Rule should apply"), "mmtd");
+    assert_eq!(apply_sound_change("[consonant,unvoiced] > [+voiced,-unvoiced] / vowel _ vowel", "patap").expect("Rule should apply"), "padap");
+    assert!(matches!(apply_sound_change("[consonant] > [+vowel]", "pa"), Err(ElbieError::NoPhonemeWithChangedFeatures(..))));
+    // deletion and insertion
+    assert_eq!(apply_sound_change("t > ∅", "tata").expect("Rule should apply"), "aa");
+    assert_eq!(apply_sound_change("t >", "tata").expect("Rule should apply"), "aa");
+    assert_eq!(apply_sound_change("∅ > e / p _ t", "ptapa").expect("Rule should apply"), "petapa");
+    // copies
+    assert_eq!(apply_sound_change("consonant vowel > 2 1", "pati").expect("Rule should apply"), "apit");
+    assert_eq!(apply_sound_change("consonant > 1 1 / vowel _ vowel", "apa").expect("Rule should apply"), "appa");
+    assert_eq!(apply_sound_change("nasal stop > 2 2", "ampa").expect("Rule should apply"), "appa");
+}
+
+#[test]
+fn test_sound_change_boundaries() {
+    assert_eq!(apply_sound_change("p > b / # _", "papap").expect("Rule should apply"), "bapap");
+    assert_eq!(apply_sound_change("p > b / _ #", "papap").expect("Rule should apply"), "papab");
+    assert_eq!(apply_sound_change("p > b / # _ #", "p").expect("Rule should apply"), "b");
+    assert_eq!(apply_sound_change("p > b / # _ #", "pa").expect("Rule should apply"), "pa");
+    assert_eq!(apply_sound_change("p > b / # a _", "apap").expect("Rule should apply"), "abap");
+    assert_eq!(apply_sound_change("p > b / _ a #", "papa").expect("Rule should apply"), "paba");
+    // the left environment is longer than the start of the word, so it can't match there.
+    assert_eq!(apply_sound_change("p > b / a a _", "ap").expect("Rule should apply"), "ap");
+    assert_eq!(apply_sound_change("p > b / a a _", "aap").expect("Rule should apply"), "aab");
+    assert_eq!(apply_sound_change("p > b / _ a a", "pa").expect("Rule should apply"), "pa");
+}
+
+#[test]
+fn test_sound_change_names_with_underscores() {
+    use crate::errors::ElbieError;
+
+    // the longest name is matched first, so this is the 'open_mid' set, and there is no '_'.
+    assert!(matches!(apply_sound_change("p > b / open_mid", "ep"), Err(ElbieError::InvalidSoundChange(..))));
+    assert_eq!(apply_sound_change("p > b / open_mid _", "epap").expect("Rule should apply"), "ebap");
+    assert_eq!(apply_sound_change("p > b / _ open_mid", "papo").expect("Rule should apply"), "pabo");
+    // without 'open_mid', names around the '_' don't need spaces.
+    assert_eq!(apply_sound_change("p > b / vowel_vowel", "papap").expect("Rule should apply"), "pabap");
+}
+
+// transforms the word with a single rule, using the language from `sound_change_language`.
+fn try_apply_rule<Sequence: Fn(&mut super::transformation::RuleState) -> Result<bool, super::transformation::RuleStateError> + 'static>(word: &str, rule: Sequence)
+                                                                                                                                        -> Result<String, super::errors::ElbieError> {
+    use crate::transformation::Transformation;
+    use crate::word::Word;
+    use std::collections::HashMap;
+
+    let language = sound_change_language();
+    let mut transformation = Transformation::from(&language);
+    transformation.add_rule("test", rule);
+    let word = language.read_word(word)?;
+    let result = transformation.transform(&word, &HashMap::new(), None)?;
+    Ok(result.iter().map(Word::to_raw_string).collect::<Vec<_>>().join("~"))
+}
+
+fn apply_rule<Sequence: Fn(&mut super::transformation::RuleState) -> Result<bool, super::transformation::RuleStateError> + 'static>(word: &str, rule: Sequence) -> String {
+    try_apply_rule(word, rule).expect("Word should be transformed")
+}
+
+#[test]
+fn test_rule_peek_behind() {
+    use crate::transformation::RuleState;
+
+    let mark_initial = |rule: &mut RuleState| {
+        let initial = rule.peek_behind(1).is_none();
+        rule.repl("a",
+                  if initial {
+                      &["e"]
+                  } else {
+                      &["a"]
+                  })?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("aaa", mark_initial), "eaa");
+    // position 0 and 1 have nothing two phonemes back.
+    let mark_near_start = |rule: &mut RuleState| {
+        let near_start = rule.peek_behind(2).is_none();
+        rule.repl("a",
+                  if near_start {
+                      &["e"]
+                  } else {
+                      &["a"]
+                  })?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("aaa", mark_near_start), "eea");
+    let after_p = |rule: &mut RuleState| {
+        let after_p = rule.peek_behind(1).map(|phoneme| phoneme.name) == Some("p");
+        rule.repl("a",
+                  if after_p {
+                      &["e"]
+                  } else {
+                      &["a"]
+                  })?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("apaa", after_p), "apea");
+    let two_after_stop = |rule: &mut RuleState| {
+        let after_stop = rule.peek_behind_is(2, "stop")?;
+        rule.repl("a",
+                  if after_stop {
+                      &["e"]
+                  } else {
+                      &["a"]
+                  })?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("apaa", two_after_stop), "apae");
+}
+
+#[test]
+fn test_rule_behind() {
+    use crate::transformation::RuleState;
+
+    let after_vowel_p = |rule: &mut RuleState| {
+        rule.behind(&["vowel", "p"])?;
+        rule.repl("a", &["e"])?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("apapa", after_vowel_p), "apepe");
+    // at position 1 there is only one phoneme behind, so the sequence can't match.
+    assert_eq!(apply_rule("pa", after_vowel_p), "pa");
+    assert_eq!(apply_rule("a", after_vowel_p), "a");
+    assert_eq!(apply_rule("ipa", after_vowel_p), "ipe");
+    assert_eq!(apply_rule("mpa", after_vowel_p), "mpa");
+    // nothing behind always matches, even at position 0.
+    let empty = |rule: &mut RuleState| {
+        rule.behind(&[])?;
+        rule.repl("a", &["e"])?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("apa", empty), "epe");
+}
+
+#[test]
+fn test_rule_opt_behind() {
+    use crate::transformation::RuleState;
+
+    let after_p = |rule: &mut RuleState| {
+        if rule.opt_behind(&["p"])? {
+            rule.repl("a", &["e"])?;
+        } else {
+            rule.repl("a", &["o"])?;
+        }
+        Ok(true)
+    };
+    assert_eq!(apply_rule("apaa", after_p), "opeo");
+    let longer = |rule: &mut RuleState| {
+        if rule.opt_behind(&["a", "p"])? {
+            rule.repl("a", &["e"])?;
+        } else {
+            rule.repl("a", &["o"])?;
+        }
+        Ok(true)
+    };
+    assert_eq!(apply_rule("apa", longer), "ope");
+    assert_eq!(apply_rule("pa", longer), "po");
+}
+
+#[test]
+fn test_rule_behind_seq() {
+    use crate::transformation::RuleState;
+
+    let after_syllable = |rule: &mut RuleState| {
+        rule.behind_seq(2, |behind| {
+                behind.is("consonant")?;
+                behind.is("vowel")?;
+                Ok(true)
+            })?;
+        rule.repl("p", &["b"])?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("papap", after_syllable), "pabab");
+    // the sequence is longer than the start of the word at positions 0 and 1.
+    assert_eq!(apply_rule("p", after_syllable), "p");
+    assert_eq!(apply_rule("ap", after_syllable), "ap");
+    assert_eq!(apply_rule("aap", after_syllable), "aap");
+    // the sequence has to end at the current position.
+    let short = |rule: &mut RuleState| {
+        rule.behind_seq(2, |behind| {
+                behind.is("vowel")?;
+                Ok(true)
+            })?;
+        rule.repl("p", &["b"])?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("aap", short), "aap");
+    // replacements in the sequence are ignored.
+    let replacing = |rule: &mut RuleState| {
+        rule.behind_seq(1, |behind| {
+                behind.repl("a", &["i"])?;
+                Ok(true)
+            })?;
+        rule.repl("p", &["b"])?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("apap", replacing), "abab");
+}
+
+#[test]
+fn test_rule_word_conditions() {
+    use crate::transformation::RuleState;
+
+    let contains = |rule: &mut RuleState| {
+        if rule.word_contains("nasal")? {
+            rule.repl("a", &["e"])?;
+        }
+        Ok(true)
+    };
+    assert_eq!(apply_rule("pama", contains), "peme");
+    assert_eq!(apply_rule("papa", contains), "papa");
+
+    let first = |rule: &mut RuleState| {
+        if rule.first_in_word_is("vowel", "open_mid")? {
+            rule.repl("a", &["i"])?;
+        }
+        Ok(true)
+    };
+    assert_eq!(apply_rule("pepa", first), "pepi");
+    assert_eq!(apply_rule("pape", first), "pape");
+    let last = |rule: &mut RuleState| {
+        if rule.last_in_word_is("vowel", "open_mid")? {
+            rule.repl("a", &["i"])?;
+        }
+        Ok(true)
+    };
+    assert_eq!(apply_rule("pape", last), "pipe");
+    assert_eq!(apply_rule("pepa", last), "pepa");
+    // no phoneme is in the set.
+    let no_vowel = |rule: &mut RuleState| {
+        if !rule.first_in_word_is("vowel", "open")? && !rule.last_in_word_is("vowel", "open")? {
+            rule.repl("p", &["b"])?;
+        }
+        Ok(true)
+    };
+    assert_eq!(apply_rule("ptp", no_vowel), "btb");
+    assert_eq!(apply_rule("pap", no_vowel), "pap");
+}
+
+#[test]
+fn test_rule_captures() {
+    use crate::transformation::RuleState;
+
+    let gemination = |rule: &mut RuleState| {
+        rule.is("vowel")?;
+        rule.capture("c", "consonant")?;
+        rule.ins_captured(&["c"])?;
+        rule.is("vowel")?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("apama", gemination), "appamma");
+    assert_eq!(apply_rule("pap", gemination), "pap");
+
+    let metathesis = |rule: &mut RuleState| {
+        let start = rule.position();
+        rule.capture("c", "consonant")?;
+        rule.capture("v", "vowel")?;
+        rule.repl_captured(start, &["v", "c"])?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("pati", metathesis), "apit");
+    // capture keys and phoneme names can be mixed.
+    let epenthesis = |rule: &mut RuleState| {
+        let start = rule.position();
+        rule.capture_seq("cluster", |cluster| {
+                cluster.is("stop")?;
+                cluster.is("stop")?;
+                Ok(true)
+            })?;
+        rule.repl_captured(start, &["a", "cluster", "a"])?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("ipti", epenthesis), "iaptai");
+    // a later capture with the same key replaces the earlier one.
+    let last_capture = |rule: &mut RuleState| {
+        let start = rule.position();
+        rule.capture("c", "consonant")?;
+        rule.capture("c", "consonant")?;
+        rule.repl_captured(start, &["c", "c"])?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("pta", last_capture), "tta");
+}
+
+#[test]
+fn test_rule_capture_keys() {
+    use crate::errors::ElbieError;
+    use crate::transformation::RuleState;
+
+    // a key which is a phoneme name would hide the phoneme in `ins_captured`, so it's not allowed.
+    let phoneme_key = |rule: &mut RuleState| {
+        rule.capture("a", "consonant")?;
+        rule.ins_captured(&["a"])?;
+        Ok(true)
+    };
+    assert!(matches!(try_apply_rule("pa", phoneme_key), Err(ElbieError::CaptureKeyIsPhoneme("a"))));
+    let phoneme_seq_key = |rule: &mut RuleState| {
+        rule.capture_seq("p", |sequence| {
+                sequence.is("consonant")?;
+                Ok(true)
+            })?;
+        Ok(true)
+    };
+    assert!(matches!(try_apply_rule("pa", phoneme_seq_key), Err(ElbieError::CaptureKeyIsPhoneme("p"))));
+    // set names are fine, since sets can't be inserted.
+    let set_key = |rule: &mut RuleState| {
+        rule.capture("vowel", "vowel")?;
+        rule.ins_captured(&["vowel"])?;
+        Ok(true)
+    };
+    assert_eq!(apply_rule("pa", set_key), "paa");
+    // a name which is neither a capture key nor a phoneme is an error.
+    let unknown_key = |rule: &mut RuleState| {
+        rule.is("consonant")?;
+        rule.ins_captured(&["c"])?;
+        Ok(true)
+    };
+    assert!(matches!(try_apply_rule("pa", unknown_key), Err(ElbieError::UnknownPhoneme("c"))));
+}
+
+#[test]
+fn test_rule_examples() {
+    use crate::errors::ElbieError;
+    use crate::transformation::Transformation;
+
+    let language = rule_application_language();
+    let mut transformation = Transformation::from(&language);
+    transformation.add_rule("fronting", |rule| {
+                      rule.repl("k", &["t"])?;
+                      Ok(true)
+                  });
+    transformation.add_rule_examples("fronting", &[("/aka/", "ata"), ("kik", "tit"), ("aka", "aka")]).expect("Examples should be read");
+    transformation.add_examples(&[("ika", "ita")]).expect("Examples should be read");
+    // unknown phonemes are reported when the examples are added, not when they're checked.
+    assert!(matches!(transformation.add_rule_examples("fronting", &[("abi", "abi")]), Err(ElbieError::UnknownPhonemeWhileReading(..))));
+    assert!(matches!(transformation.add_rule_examples("fronting", &[("aki", "ati~abi")]), Err(ElbieError::UnknownPhonemeWhileReading(..))));
+    assert!(matches!(transformation.add_examples(&[("abi", "abi")]), Err(ElbieError::UnknownPhonemeWhileReading(..))));
+    assert!(matches!(transformation.add_rule_examples("backing", &[("aka", "aka")]), Err(ElbieError::UnknownTransformationRule("backing"))));
+
+    let passed = transformation.check_examples().iter().map(|example| (example.rule, example.passed())).collect::<Vec<_>>();
+    assert_eq!(passed, [(Some("fronting"), true), (Some("fronting"), true), (Some("fronting"), false), (None, true)]);
+}
+
+#[test]
+fn test_find_transformation_chain() {
+    use crate::errors::ElbieError;
+    use crate::family::Family;
+    use crate::transformation::Transformation;
+    use crate::word::Word;
+    use std::collections::HashMap;
+
+    // each transformation replaces one phoneme and validates as the next language.
+    fn add_step(family: &mut Family, from: &'static str, to: &'static str, phoneme: &'static str, replace: &'static str) {
+        family.transformation(from, to, move |family| {
+                  family.load_language(from)?;
+                  let mut transformation = Transformation::from(family.get_language(from)?);
+                  transformation.set_validation_language(Some(to));
+                  transformation.add_rule("change", move |rule| {
+                                    rule.repl(phoneme, &[replace])?;
+                                    Ok(true)
+                                });
+                  Ok(transformation)
+              })
+              .expect("Transformation should be added");
+    }
+
+    let mut family = Family::default();
+    for name in ["old", "middle", "new"] {
+        family.language(name, move || Ok(named_language(name))).expect("Language should be added");
+    }
+    add_step(&mut family, "old", "middle", "k", "t");
+    add_step(&mut family, "middle", "new", "a", "i");
+    // a dead end, which the search has to look past.
+    add_step(&mut family, "old", "other", "t", "k");
+
+    // the chain is only found when asked for.
+    assert!(matches!(family.load_transformation("old", "new"), Err(ElbieError::UnknownTransformation(..))));
+    let steps = family.find_transformation_chain("old", "new").expect("Chain should be found");
+    assert_eq!(steps, [("old".to_owned(), "middle".to_owned()), ("middle".to_owned(), "new".to_owned())]);
+    assert!(!family.transformation_keys().contains(&("old".to_owned(), "new".to_owned())));
+    assert!(matches!(family.load_transformation("old", "new"), Err(ElbieError::UnknownTransformation(..))));
+    assert!(matches!(family.find_transformation_chain("new", "old"), Err(ElbieError::UnknownTransformation(..))));
+
+    family.load_language("old").expect("Language should be loaded");
+    let transformations = family.get_chain_transformations(&steps, false).expect("Chain should be prepared");
+    assert_eq!(transformations.iter().map(|item| (item.name.as_str(), item.chained)).collect::<Vec<_>>(), [("middle", false), ("new", true)]);
+    let mut word = family.get_language("old").expect("Language should be loaded").read_word("kak").expect("Word should be read");
+    for item in &transformations {
+        word = item.transformation.transform(&word, &HashMap::new(), None).expect("Word should be transformed").remove(0);
+    }
+    assert_eq!(Word::to_raw_string(&word), "tit");
+}
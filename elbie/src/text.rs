@@ -0,0 +1,147 @@
+use crate::errors::ElbieError;
+use crate::language::Language;
+use crate::word::Word;
+use core::iter;
+
+/* NOTE:
+
+Texts are written phonemically, the same way words are, with some extra notation:
+- Whitespace separates words. Line breaks are kept in the output.
+- Punctuation at the start or end of a word is passed through as is. A '.', '!' or '?' ends a sentence.
+- A '+' inside a word marks a morpheme boundary. The morphemes are spelled as one word, unless the orthography has a morpheme separator. If it does, and the orthography is made by a transformation, each morpheme is spelled separately, so the rules can't match across the boundary.
+- A '*' at the start of a word marks it as a proper noun.
+- Slashes are ignored, so text can be written as '/wɜd wɜd/' if you prefer.
+
+*/
+
+pub const MORPHEME_BOUNDARY: char = '+';
+pub const PROPER_NOUN_MARK: char = '*';
+const PHONEMIC_DELIMITER: char = '/';
+const PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', '"', '“', '”', '‘', '’', '(', ')', '[', ']', '«', '»', '¡', '¿', '…', '—', '–', PHONEMIC_DELIMITER];
+const SENTENCE_END: &[char] = &['.', '!', '?'];
+
+/// Controls how running text is put together in an orthography, see `Language::set_text_style`.
+#[derive(Debug, Clone)]
+pub struct TextStyle {
+    word_separator: &'static str,
+    morpheme_separator: &'static str,
+    capitalize_sentences: bool,
+    capitalize_proper_nouns: bool
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self { word_separator: " ",
+               morpheme_separator: "",
+               capitalize_sentences: true,
+               capitalize_proper_nouns: true }
+    }
+}
+
+impl TextStyle {
+    #[must_use]
+    pub const fn new(word_separator: &'static str, morpheme_separator: &'static str, capitalize_sentences: bool, capitalize_proper_nouns: bool) -> Self {
+        Self { word_separator,
+               morpheme_separator,
+               capitalize_sentences,
+               capitalize_proper_nouns }
+    }
+}
+
+fn is_punctuation(c: char) -> bool {
+    PUNCTUATION.contains(&c)
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+}
+
+fn spell_text_word(language: &Language, text: &str, orthography: usize, style: &TextStyle) -> Result<String, ElbieError> {
+    let mut phonemes = Vec::new();
+    let mut boundaries = Vec::new();
+    for morpheme in text.split(MORPHEME_BOUNDARY) {
+        if !phonemes.is_empty() {
+            boundaries.push(phonemes.len());
+        }
+        phonemes.extend(language.read_word(morpheme)?.phonemes().iter().cloned());
+    }
+    let word = Word::from(phonemes);
+
+    // Without a separator, the morphemes don't need to be found, so the rules can see the whole word.
+    if style.morpheme_separator.is_empty() || boundaries.is_empty() {
+        return language.spell_word(&word, orthography);
+    }
+
+    // The rules of a transformed orthography can change the number of letters, so the morphemes can only be found by spelling them separately.
+    if language.orthography_transformation(orthography).is_some() {
+        let mut result = Vec::new();
+        let mut start = 0;
+        for end in boundaries.into_iter().chain(iter::once(word.phonemes().len())) {
+            result.push(language.spell_word(&Word::from(word.phonemes().get(start..end).unwrap_or_default().to_vec()), orthography)?);
+            start = end;
+        }
+        return Ok(result.join(style.morpheme_separator));
+    }
+
+    // This is the same as Language::spell_word, except it has to keep track of where the morphemes start. A spelling callback might consume
+    // more than one phoneme, so the position has to come from what's left in the iterator.
+    let mut result = String::new();
+    let mut boundaries = boundaries.into_iter().peekable();
+    let mut iter = word.phonemes().iter().peekable();
+    while let Some(phoneme) = iter.next() {
+        language.spell_phoneme(phoneme, orthography, &mut result, Some(&mut iter))?;
+        let position = word.phonemes().len() - iter.len();
+        while boundaries.next_if(|boundary| *boundary <= position).is_some() {
+            result.push_str(style.morpheme_separator);
+        }
+    }
+    Ok(result)
+}
+
+/// Spells a phonemic text (see the notes at the top of this file) in the specified orthography.
+pub(crate) fn spell_text(language: &Language, text: &str, orthography: usize) -> Result<String, ElbieError> {
+    let style = language.text_style(orthography).ok_or(ElbieError::UnknownOrthography(orthography))?;
+
+    let mut result = String::new();
+    let mut sentence_start = true;
+
+    for (line_index, line) in text.lines().enumerate() {
+        for (word_index, chunk) in line.split_whitespace().enumerate() {
+            if word_index > 0 {
+                result.push_str(style.word_separator);
+            }
+
+            let after_leading = chunk.trim_start_matches(is_punctuation);
+            let leading = chunk.strip_suffix(after_leading).unwrap_or_default();
+            let body = after_leading.trim_end_matches(is_punctuation);
+            let trailing = after_leading.strip_prefix(body).unwrap_or_default();
+
+            result.extend(leading.chars().filter(|c| *c != PHONEMIC_DELIMITER));
+
+            let (body, proper_noun) = match body.strip_prefix(PROPER_NOUN_MARK) {
+                Some(body) => (body, true),
+                None => (body, false)
+            };
+
+            if !body.is_empty() {
+                let spelled = spell_text_word(language, body, orthography, style).map_err(|err| ElbieError::TextParsingError(line_index + 1, err.into()))?;
+                if (sentence_start && style.capitalize_sentences) || (proper_noun && style.capitalize_proper_nouns) {
+                    result.push_str(&capitalize(&spelled));
+                } else {
+                    result.push_str(&spelled);
+                }
+                sentence_start = false;
+            }
+
+            result.extend(trailing.chars().filter(|c| *c != PHONEMIC_DELIMITER));
+
+            if trailing.contains(SENTENCE_END) {
+                sentence_start = true;
+            }
+        }
+        result.push('\n');
+    }
+
+    Ok(result)
+}
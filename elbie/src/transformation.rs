@@ -2,12 +2,15 @@ use crate::errors::ElbieError;
 use crate::language::Language;
 use crate::phoneme::Inventory;
 use crate::phoneme::Phoneme;
+use crate::sound_change::SoundChange;
 use crate::word::Word;
 use core::fmt;
 use core::fmt::Display;
 use core::fmt::Formatter;
 use core::iter::Peekable;
+use core::iter::once;
 use core::slice::Iter;
+use std::collections::HashMap;
 use std::rc::Rc;
 
 pub(crate) enum TransformationTraceMessage {
@@ -28,6 +31,9 @@ impl Display for TransformationTraceMessage {
 
 pub(crate) type TransformationTraceCallback = dyn Fn(TransformationTraceMessage);
 
+/// The name of each rule in a transformation, and the variants after it was applied, or None if the rule didn't change them. See `Transformation::derive`.
+pub(crate) type Derivation = Vec<(&'static str, Option<Vec<Word>>)>;
+
 struct WordSplice {
     start_index: usize,
     length: usize,
@@ -47,17 +53,23 @@ impl From<ElbieError> for RuleStateError {
 
 pub struct RuleState<'phonemes> {
     inventory: &'phonemes Inventory,
+    word: &'phonemes [Rc<Phoneme>],
+    attributes: &'phonemes HashMap<String, String>,
     phonemes: Peekable<Iter<'phonemes, Rc<Phoneme>>>,
     word_index: usize,
-    splices: Vec<WordSplice>
+    splices: Vec<WordSplice>,
+    captures: Vec<(&'static str, Vec<Rc<Phoneme>>)>
 }
 
 impl<'phonemes> RuleState<'phonemes> {
-    const fn new(inventory: &'phonemes Inventory, phonemes: Peekable<Iter<'phonemes, Rc<Phoneme>>>, word_index: usize) -> Self {
+    fn new(inventory: &'phonemes Inventory, word: &'phonemes [Rc<Phoneme>], attributes: &'phonemes HashMap<String, String>, word_index: usize) -> Self {
         Self { inventory,
-               phonemes,
+               word,
+               attributes,
+               phonemes: word.get(word_index..).unwrap_or_default().iter().peekable(),
                word_index,
-               splices: Vec::new() }
+               splices: Vec::new(),
+               captures: Vec::new() }
     }
 }
 
@@ -132,6 +144,106 @@ impl RuleState<'_> {
         }
     }
 
+    /// Returns the phoneme the specified distance behind the current position, where 1 is the phoneme just before it. The position is not changed.
+    #[must_use]
+    pub fn peek_behind(&self, distance: usize) -> Option<&Rc<Phoneme>> {
+        let index = self.word_index.checked_sub(distance)?;
+        self.word.get(index)
+    }
+
+    /// Returns true if the phoneme the specified distance behind the current position matches the specified name, or is in a set with that name. See `peek_is` and `peek_behind`.
+    pub fn peek_behind_is(&mut self, distance: usize, name: &'static str) -> Result<bool, ElbieError> {
+        if let Some(phoneme) = self.peek_behind(distance).cloned() {
+            self.phoneme_is(&phoneme, name)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Matches the phonemes just before the current position, without moving the position or making them part of the match. The names are in word order, so `rule.behind(&[VOWEL, NASAL])?` matches if the current position follows a vowel and a nasal. Returns a MatchFailed error if they don't match.
+    pub fn behind(&mut self, names: &[&'static str]) -> Result<(), RuleStateError> {
+        for (distance, name) in (1..=names.len()).rev().zip(names) {
+            if !self.peek_behind_is(distance, name)? {
+                return Err(RuleStateError::MatchFailed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Uses `behind` to match the preceding phonemes, returning false instead of a MatchFailed error.
+    pub fn opt_behind(&mut self, names: &[&'static str]) -> Result<bool, ElbieError> {
+        match self.behind(names) {
+            Ok(()) => Ok(true),
+            Err(RuleStateError::MatchFailed) => Ok(false),
+            Err(RuleStateError::Elbie(err)) => Err(err)
+        }
+    }
+
+    /// Processes the closure as a sequence starting `length` phonemes behind the current position, which must match exactly up to the current position. This allows more complicated lookbehind than `behind`. The position is not changed, and any replacements made in the closure are ignored. Captures made in the closure are kept, so a rule can copy a preceding phoneme.
+    pub fn behind_seq<Sequence: Fn(&mut Self) -> Result<bool, RuleStateError>>(&mut self, length: usize, sequence: Sequence) -> Result<(), RuleStateError> {
+        let start = self.word_index.checked_sub(length).ok_or(RuleStateError::MatchFailed)?;
+        let mut inner = Self::new(self.inventory, self.word, self.attributes, start);
+        inner.captures.clone_from(&self.captures);
+        inner.seq(sequence)?;
+        if inner.word_index == self.word_index {
+            self.captures = inner.captures;
+            Ok(())
+        } else {
+            Err(RuleStateError::MatchFailed)
+        }
+    }
+
+    /// The whole word being transformed, for conditions which can't be expressed with the other functions.
+    #[must_use]
+    pub const fn word(&self) -> &[Rc<Phoneme>] {
+        self.word
+    }
+
+    /// Returns the value of an attribute of the word being transformed, such as a column from the CSV file the word was read from. Returns None if the word doesn't have the attribute.
+    #[must_use]
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    /// If the word has the specified attribute with the specified value, returns Ok. Otherwise, returns a MatchFailed error. This allows a rule to only apply to some words, such as `rule.attribute_is("part-of-speech", "noun")?`. To condition the entire rule, see `Transformation::add_rule_condition`.
+    pub fn attribute_is(&self, name: &str, value: &str) -> Result<(), RuleStateError> {
+        if self.attribute(name) == Some(value) {
+            Ok(())
+        } else {
+            Err(RuleStateError::MatchFailed)
+        }
+    }
+
+    /// Returns true if any phoneme in the word matches the specified name, or is in a set with that name. The position is not changed.
+    pub fn word_contains(&mut self, name: &'static str) -> Result<bool, ElbieError> {
+        for phoneme in self.word {
+            if self.phoneme_is(phoneme, name)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Finds the last phoneme in the word which is in `set`, and returns true if it matches `name`. For example, `rule.last_in_word_is(VOWEL, FRONT)?` checks if the last vowel of the word is front. Returns false if no phoneme is in the set.
+    pub fn last_in_word_is(&mut self, set: &'static str, name: &'static str) -> Result<bool, ElbieError> {
+        for phoneme in self.word.iter().rev() {
+            if self.phoneme_is(phoneme, set)? {
+                return self.phoneme_is(phoneme, name);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Finds the first phoneme in the word which is in `set`, and returns true if it matches `name`. See `last_in_word_is`.
+    pub fn first_in_word_is(&mut self, set: &'static str, name: &'static str) -> Result<bool, ElbieError> {
+        for phoneme in self.word {
+            if self.phoneme_is(phoneme, set)? {
+                return self.phoneme_is(phoneme, name);
+            }
+        }
+        Ok(false)
+    }
+
     /// Matches any phoneme. If there is a phoneme in the iterator, it shifts the position forward and returns Ok. Otherwise, returns a MatchFailed error.
     pub fn any(&mut self) -> Result<(), RuleStateError> {
         if self.phonemes.next().is_some() {
@@ -181,14 +293,18 @@ impl RuleState<'_> {
     /// Creates a new Pattern based off of the current state, and processes it using `seq`. If the match succeeds, the state is merged back into the main Pattern and true is returned. If the match fails, the state is not merged back in, but false is returned, indicating a successful but empty match.
     pub fn opt_seq<Sequence: Fn(&mut Self) -> Result<bool, RuleStateError>>(&mut self, sequence: Sequence) -> Result<bool, ElbieError> {
         let mut inner = Self { inventory: self.inventory,
+                               word: self.word,
+                               attributes: self.attributes,
                                phonemes: self.phonemes.clone(),
                                word_index: self.word_index,
-                               splices: Vec::new() };
+                               splices: Vec::new(),
+                               captures: self.captures.clone() };
         match inner.seq(sequence) {
             Ok(()) => {
                 self.phonemes = inner.phonemes;
                 self.word_index = inner.word_index;
                 self.splices.extend(inner.splices);
+                self.captures = inner.captures;
                 Ok(true)
             },
             Err(RuleStateError::MatchFailed) => Ok(false),
@@ -196,10 +312,107 @@ impl RuleState<'_> {
         }
     }
 
+    /// The index of the next phoneme in the word. Save this before matching to pass to `repl_captured`.
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.word_index
+    }
+
+    pub(crate) fn splice(&mut self, start_index: usize, length: usize, replace: Vec<Rc<Phoneme>>) {
+        self.splices.push(WordSplice { start_index,
+                                       length,
+                                       replace });
+    }
+
+    pub(crate) fn find_with_changed_features(&self, phoneme: &Rc<Phoneme>, remove: &[&'static str], add: &[&'static str]) -> Result<Rc<Phoneme>, ElbieError> {
+        self.inventory.find_with_changed_features(phoneme, remove, add)
+    }
+
     fn get_replacement(&self, phonemes: &[&'static str]) -> Result<Vec<Rc<Phoneme>>, ElbieError> {
         phonemes.iter().map(|phoneme| self.inventory.get_phoneme(phoneme).cloned()).collect()
     }
 
+    fn get_captured_replacement(&self, names: &[&'static str]) -> Result<Vec<Rc<Phoneme>>, ElbieError> {
+        let mut result = Vec::new();
+        for name in names {
+            if let Some(captured) = self.captured(name) {
+                result.extend_from_slice(captured);
+            } else {
+                result.push(self.inventory.get_phoneme(name)?.clone());
+            }
+        }
+        Ok(result)
+    }
+
+    // capture keys can't be phoneme names, otherwise `ins_captured` and `repl_captured` couldn't tell them apart.
+    fn check_capture_key(&self, key: &'static str) -> Result<(), ElbieError> {
+        if self.inventory.get_phoneme(key).is_ok() {
+            Err(ElbieError::CaptureKeyIsPhoneme(key))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn add_capture(&mut self, key: &'static str, start_index: usize) {
+        let captured = self.word.get(start_index..self.word_index).unwrap_or_default().to_vec();
+        // a later capture with the same key replaces the earlier one, which matters when a sequence is repeated.
+        self.captures.retain(|(existing, _)| *existing != key);
+        self.captures.push((key, captured));
+    }
+
+    /// Calls `is`, and if there is a match remembers the matched phoneme under the specified key, so it can be copied with `ins_captured` or `repl_captured`. Capturing doesn't replace anything. Returns an error if the key is the name of a phoneme.
+    pub fn capture(&mut self, key: &'static str, name: &'static str) -> Result<(), RuleStateError> {
+        self.check_capture_key(key)?;
+        let start_index = self.word_index;
+        self.is(name)?;
+        self.add_capture(key, start_index);
+        Ok(())
+    }
+
+    /// Calls `seq`, and if it matches remembers all of the phonemes matched under the specified key. See `capture`.
+    pub fn capture_seq<Sequence: Fn(&mut Self) -> Result<bool, RuleStateError>>(&mut self, key: &'static str, sequence: Sequence) -> Result<(), RuleStateError> {
+        self.check_capture_key(key)?;
+        let start_index = self.word_index;
+        self.seq(sequence)?;
+        self.add_capture(key, start_index);
+        Ok(())
+    }
+
+    /// Returns the phonemes captured under the specified key, if anything has been captured with it.
+    #[must_use]
+    pub fn captured(&self, key: &str) -> Option<&[Rc<Phoneme>]> {
+        self.captures.iter().rev().find(|(existing, _)| *existing == key).map(|(_, phonemes)| phonemes.as_slice())
+    }
+
+    /// Like `ins`, except each name can also be a capture key, which inserts a copy of the captured phonemes. Capture keys can't be phoneme names, see `capture`. For example, gemination is `rule.capture("c", CONSONANT)?; rule.ins_captured(&["c"])?;`.
+    pub fn ins_captured(&mut self, names: &[&'static str]) -> Result<(), ElbieError> {
+        let replace = self.get_captured_replacement(names)?;
+
+        self.splices.push(WordSplice { start_index: self.word_index,
+                                       length: 0,
+                                       replace });
+
+        Ok(())
+    }
+
+    /// Adds a new splice replacing everything from `start_index` (a value returned by `position`) to the current position with the specified phonemes, where each name can also be a capture key. For example, metathesis of a consonant and a vowel is:
+    ///
+    /// ```text
+    /// let start = rule.position();
+    /// rule.capture("c", CONSONANT)?;
+    /// rule.capture("v", VOWEL)?;
+    /// rule.repl_captured(start, &["v", "c"])?;
+    /// ```
+    pub fn repl_captured(&mut self, start_index: usize, names: &[&'static str]) -> Result<(), ElbieError> {
+        let replace = self.get_captured_replacement(names)?;
+
+        self.splices.push(WordSplice { start_index,
+                                       length: self.word_index.saturating_sub(start_index),
+                                       replace });
+
+        Ok(())
+    }
+
     /// Adds a new splice at the current position that replaces a length of 0 and contains the specified phonemes. Will return an error if the phonemes do not exist in the inventory.
     pub fn ins(&mut self, phonemes: &[&'static str]) -> Result<(), ElbieError> {
         let replace = self.get_replacement(phonemes)?;
@@ -239,6 +452,30 @@ impl RuleState<'_> {
         Ok(matched)
     }
 
+    /// Calls `is`, and if there is a match adds a new splice to replace the matching phoneme with the phoneme that has the same sets, except that `from` is swapped for `to`. For example, `rule.repl_feature(CONSONANT, UNVOICED, VOICED)` voices a consonant. The sets compared are only those the phonemes were added with. Will return an error if there isn't exactly one such phoneme in the inventory.
+    pub fn repl_feature(&mut self, name: &'static str, from: &'static str, to: &'static str) -> Result<(), RuleStateError> {
+        let start_index = self.word_index;
+        let phoneme = self.peek().cloned();
+        self.is(name)?;
+        let phoneme = phoneme.ok_or(RuleStateError::MatchFailed)?;
+
+        let replace = self.inventory.find_with_changed_features(&phoneme, &[from], &[to])?;
+        self.splices.push(WordSplice { start_index,
+                                       length: 1,
+                                       replace: vec![replace] });
+
+        Ok(())
+    }
+
+    /// Calls `repl_feature`, returning true if there was a match. If the match failed, no replacement is made and returns false. Will still return an error if there isn't exactly one phoneme with the changed feature.
+    pub fn opt_repl_feature(&mut self, name: &'static str, from: &'static str, to: &'static str) -> Result<bool, ElbieError> {
+        match self.repl_feature(name, from, to) {
+            Ok(()) => Ok(true),
+            Err(RuleStateError::MatchFailed) => Ok(false),
+            Err(RuleStateError::Elbie(err)) => Err(err)
+        }
+    }
+
     /// Calls `seq` with the specified closure, and if it matches adds a new splice which replaces the match with the specified phonemes. Returns Ok if the sequence that matched. If the sequence does not match, no replacement is done and the function returns MatchFailed.
     pub fn repl_seq<Sequence: Fn(&mut Self) -> Result<bool, RuleStateError>>(&mut self, sequence: Sequence, phonemes: &[&'static str]) -> Result<(), RuleStateError> {
         let replace = self.get_replacement(phonemes)?;
@@ -272,54 +509,85 @@ impl RuleState<'_> {
 
 type Sequence = Box<dyn Fn(&mut RuleState) -> Result<bool, RuleStateError>>;
 
+// a rule applied directionally or iteratively that is still changing the word after this many applications is probably never going to stop.
+const MAX_RULE_APPLICATIONS: usize = 1000;
+
+/// Controls how a rule is applied to a word. See `Transformation::set_rule_application`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RuleApplication {
+    /// All matches are found in the original word, and then replaced at once. Overlapping replacements are an error.
+    #[default]
+    Simultaneous,
+    /// Matches are tested from the start of the word, and each one is replaced before testing the next, so a replacement can create or destroy the environment for the next match. Matching resumes after the last replacement, so a rule can't match its own output, and `k > kk` doesn't go on forever.
+    LeftToRight,
+    /// Like `LeftToRight`, except matches are tested from the end of the word to the start.
+    RightToLeft,
+    /// The rule is applied simultaneously, over and over, until the word stops changing.
+    Iterative
+}
+
+/// Controls whether a rule always changes the words it matches. See `Transformation::set_rule_variation`.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum RuleVariation {
+    /// The rule always applies.
+    #[default]
+    Always,
+    /// The rule is optional, so the words are output both with and without the change, in free variation.
+    Optional,
+    /// The rule applies to each word with the specified probability, between 0 and 1. This is for sporadic changes. Whether it applies is decided from the word and the transformation's seed, so a word is transformed the same way every time, see `Transformation::set_seed`.
+    Probability(f64)
+}
+
 pub struct Rule {
     name: &'static str,
-    sequence: Sequence
+    sequence: Sequence,
+    application: RuleApplication,
+    variation: RuleVariation,
+    // other ways of applying the rule, each of which produces another variant.
+    alternatives: Vec<Self>,
+    // raw phonemic forms of words the rule doesn't apply to
+    exceptions: Vec<Word>,
+    // attribute name, value and whether the value is required (true) or excluded (false)
+    conditions: Vec<(String, String, bool)>,
+    // input and expected output variants
+    examples: Vec<(Word, Vec<Word>)>
 }
 
 impl Rule {
     #[must_use]
     fn new<Sequence: Fn(&mut RuleState) -> Result<bool, RuleStateError> + 'static>(name: &'static str, sequence: Sequence) -> Self {
         Self { name,
-               sequence: Box::new(sequence) }
+               sequence: Box::new(sequence),
+               application: RuleApplication::default(),
+               variation: RuleVariation::default(),
+               alternatives: Vec::new(),
+               exceptions: Vec::new(),
+               conditions: Vec::new(),
+               examples: Vec::new() }
     }
 
-    /**
-    This isn't the same algorithm as a string replace, there are some differences.
-
-    A string replace function generally replaces one at a time, matches can not overlap. `"sss".replace("ss","test")` results in `"tests", not "testtest" or something weird, even though the last two characters also match.
-
-    However, since the rules for language change transform based on the environment around the replacement, I can't do that here. Say we have a sound change rule `CVhC` become `CesC`, and we apply it to the word /tuhtiht/. The result should be /testest/. However, with string replace rules, one would get /testiht/. The second syllable wouldn't match because it was already part of the previous match.
-
-    To fix this, the matches are tested starting from each phoneme, on the original word, and the replacements are spliced in after all the matches have been tested.
-
-    One complication added to this change is a possibility of this resulting in overlapping replacements if the user defining the rule is not careful. Overlapping replacements will be reported as an error rather than try to guess what the user really meant.
-    */
-    fn transform(&self, transformer: &Transformation, word: Word, trace: Option<&TransformationTraceCallback>) -> Result<Word, ElbieError> {
-        let mut phonemes = word.phonemes().iter();
-        let mut current_index = 0;
-        let mut splices = Vec::new();
+    // The original word is used to check exceptions, since the word the rule sees may have already been changed by earlier rules.
+    fn applies_to(&self, original: &Word, attributes: &HashMap<String, String>) -> bool {
+        if self.exceptions.contains(original) {
+            return false;
+        }
+        self.conditions.iter().all(|(name, value, required)| (attributes.get(name) == Some(value)) == *required)
+    }
 
-        loop {
-            // clone the enumerator to store it's current position, so the normal iterator isn't incremented.
-            let match_phonemes = phonemes.clone().peekable();
-            let mut state = RuleState::new(&transformer.inventory, match_phonemes, current_index);
-            match (self.sequence)(&mut state) {
-                Ok(true) => {
-                    splices.extend(state.splices);
-                },
-                Ok(false) | Err(RuleStateError::MatchFailed) => (),
-                Err(RuleStateError::Elbie(err)) => return Err(err)
-            }
+    // Runs the rule starting at the specified index, and returns any splices it wants to make.
+    fn find_splices(&self, transformer: &Transformation, phonemes: &[Rc<Phoneme>], attributes: &HashMap<String, String>, index: usize) -> Result<Vec<WordSplice>, ElbieError> {
+        let mut state = RuleState::new(&transformer.inventory, phonemes, attributes, index);
+        match (self.sequence)(&mut state) {
+            Ok(true) => Ok(state.splices),
+            Ok(false) | Err(RuleStateError::MatchFailed) => Ok(Vec::new()),
+            Err(RuleStateError::Elbie(err)) => Err(err)
+        }
+    }
 
-            // iterate the enumerator, if it's none we're done, otherwise we keep trying to match with the next phoneme
-            // NOTE: this would return None only if the iteration we just went through was also none. Which means we do an extra
-            // loop at the end. But the instructions shouldn't match anything (although there's a small possibility they do if the match
-            // was optional) and everything should sort of fall through without having done anything.
-            if phonemes.next().is_none() {
-                break;
-            }
-            current_index += 1;
+    // Returns None if there were no splices.
+    fn apply_splices(&self, phonemes: &[Rc<Phoneme>], mut splices: Vec<WordSplice>) -> Result<Option<Vec<Rc<Phoneme>>>, ElbieError> {
+        if splices.is_empty() {
+            return Ok(None);
         }
 
         // sort splices first, to make finding the overlaps a little easier. (We'll need them sorted anyway.)
@@ -331,6 +599,7 @@ impl Rule {
         // now, /s/ + /s/ + VOICED could become /z/ + /z/ + VOICED, even though the first /s/ wasn't covered by the rule.
         // There are still ways around it (a temporary phoneme might work).
         // However, if I deduplicate the splices before checking for overlaps, then suddenly the rule above will work.
+        // With lookbehind (`RuleState::behind`), the triggering phoneme doesn't need to be part of the match, so this is less of an issue.
         splices.dedup_by(|a, b| b.start_index == a.start_index && b.length == a.length && b.replace == a.replace);
 
         // now find all overlaps.
@@ -348,8 +617,7 @@ impl Rule {
 
         // the splices are now sorted, and unique, so I should be able to iterate through the word again and copy things in somehow...
         let mut new_phonemes = Vec::new();
-        let mut old_phonemes = word.phonemes().iter().enumerate().peekable();
-        let mut transformed = false;
+        let mut old_phonemes = phonemes.iter().enumerate().peekable();
         for next_splice in splices {
             // push through all the phonemes before the next splice and just push them through.
             while let Some((_, phoneme)) = old_phonemes.next_if(|(i, _)| i < &next_splice.start_index) {
@@ -361,29 +629,176 @@ impl Rule {
             }
             // and insert the phonemes to be replaced
             new_phonemes.extend(next_splice.replace);
-            transformed = true;
         }
         // push the remaining phonemes, after the last splice, onto the new phonemes.
         new_phonemes.extend(old_phonemes.map(|(_, p)| p.clone()));
 
-        let transformed_word = Word::from(new_phonemes);
+        Ok(Some(new_phonemes))
+    }
+
+    /**
+    This isn't the same algorithm as a string replace, there are some differences.
+
+    A string replace function generally replaces one at a time, matches can not overlap. `"sss".replace("ss","test")` results in `"tests", not "testtest" or something weird, even though the last two characters also match.
+
+    However, since the rules for language change transform based on the environment around the replacement, I can't do that here. Say we have a sound change rule `CVhC` become `CesC`, and we apply it to the word /tuhtiht/. The result should be /testest/. However, with string replace rules, one would get /testiht/. The second syllable wouldn't match because it was already part of the previous match.
+
+    To fix this, the matches are tested starting from each phoneme, on the original word, and the replacements are spliced in after all the matches have been tested.
+
+    One complication added to this change is a possibility of this resulting in overlapping replacements if the user defining the rule is not careful. Overlapping replacements will be reported as an error rather than try to guess what the user really meant.
+    */
+    fn transform_simultaneous(&self, transformer: &Transformation, phonemes: &[Rc<Phoneme>], attributes: &HashMap<String, String>) -> Result<Option<Vec<Rc<Phoneme>>>, ElbieError> {
+        let mut splices = Vec::new();
+        // NOTE: This tests one position past the end of the word, in case the rule only inserts at the end.
+        for index in 0..=phonemes.len() {
+            splices.extend(self.find_splices(transformer, phonemes, attributes, index)?);
+        }
+        self.apply_splices(phonemes, splices)
+    }
+
+    fn transform_left_to_right(&self, transformer: &Transformation, phonemes: &[Rc<Phoneme>], attributes: &HashMap<String, String>) -> Result<Option<Vec<Rc<Phoneme>>>, ElbieError> {
+        let mut current = phonemes.to_vec();
+        let mut transformed = false;
+        let mut applications = 0;
+        let mut index = 0;
+        while index <= current.len() {
+            let splices = self.find_splices(transformer, &current, attributes, index)?;
+            // the phonemes after the last splice aren't changed, so counting them back from the end of the new word finds where the replacement ended.
+            let unchanged = splices.iter().map(|splice| splice.start_index + splice.length).max().map(|end| current.len().saturating_sub(end));
+            if let Some(changed) = self.apply_splices(&current, splices)? {
+                index = if changed == current {
+                    index + 1
+                } else {
+                    // a deletion leaves this at the same index, so the phoneme that moved into it is matched next.
+                    changed.len().saturating_sub(unchanged.unwrap_or_default()).max(index)
+                };
+                current = changed;
+                transformed = true;
+                applications += 1;
+                if applications > MAX_RULE_APPLICATIONS {
+                    return Err(ElbieError::TransformationDidNotFinish(self.name, MAX_RULE_APPLICATIONS));
+                }
+            } else {
+                index += 1;
+            }
+        }
+        Ok(transformed.then_some(current))
+    }
+
+    fn transform_right_to_left(&self, transformer: &Transformation, phonemes: &[Rc<Phoneme>], attributes: &HashMap<String, String>) -> Result<Option<Vec<Rc<Phoneme>>>, ElbieError> {
+        let mut current = phonemes.to_vec();
+        let mut transformed = false;
+        // Changes can only be made at or after the index, so the positions before it are still good after a change.
+        for index in (0..=phonemes.len()).rev() {
+            let splices = self.find_splices(transformer, &current, attributes, index)?;
+            if let Some(changed) = self.apply_splices(&current, splices)? {
+                current = changed;
+                transformed = true;
+            }
+        }
+        Ok(transformed.then_some(current))
+    }
+
+    fn transform_iterative(&self, transformer: &Transformation, phonemes: &[Rc<Phoneme>], attributes: &HashMap<String, String>) -> Result<Option<Vec<Rc<Phoneme>>>, ElbieError> {
+        let mut current = phonemes.to_vec();
+        let mut transformed = false;
+        for _ in 0..MAX_RULE_APPLICATIONS {
+            match self.transform_simultaneous(transformer, &current, attributes)? {
+                // the splices might replace phonemes with the same phonemes, which still counts as a match, but not as a change.
+                Some(changed) if changed != current => {
+                    current = changed;
+                    transformed = true;
+                },
+                Some(_) => return Ok(Some(current)),
+                None => return Ok(transformed.then_some(current))
+            }
+        }
+        Err(ElbieError::TransformationDidNotFinish(self.name, MAX_RULE_APPLICATIONS))
+    }
+
+    fn transform(&self, transformer: &Transformation, word: Word, attributes: &HashMap<String, String>, trace: Option<&TransformationTraceCallback>) -> Result<Word, ElbieError> {
+        let phonemes = word.phonemes();
+        let transformed = match self.application {
+            RuleApplication::Simultaneous => self.transform_simultaneous(transformer, phonemes, attributes)?,
+            RuleApplication::LeftToRight => self.transform_left_to_right(transformer, phonemes, attributes)?,
+            RuleApplication::RightToLeft => self.transform_right_to_left(transformer, phonemes, attributes)?,
+            RuleApplication::Iterative => self.transform_iterative(transformer, phonemes, attributes)?
+        };
 
         if let Some(trace) = trace {
-            trace(if transformed {
-                      TransformationTraceMessage::MatchedRule(self.name, word, transformed_word.clone())
-                  } else {
-                      TransformationTraceMessage::UnmatchedRule(self.name)
+            trace(match &transformed {
+                      Some(new_phonemes) => TransformationTraceMessage::MatchedRule(self.name, word.clone(), Word::from(new_phonemes.clone())),
+                      None => TransformationTraceMessage::UnmatchedRule(self.name)
                   });
         }
 
-        Ok(transformed_word)
+        Ok(transformed.map_or(word, Word::from))
+    }
+
+    // Returns every variant the rule produces, without duplicates. The first one is the word as changed by the main rule, unless the rule didn't apply at all.
+    fn transform_variants(&self, transformer: &Transformation, word: Word, original: &Word, attributes: &HashMap<String, String>, trace: Option<&TransformationTraceCallback>)
+                          -> Result<Vec<Word>, ElbieError> {
+        let skipped = !self.applies_to(original, attributes) || matches!(self.variation, RuleVariation::Probability(probability) if !transformer.random_bool(self.name, original, probability));
+        if skipped {
+            if let Some(trace) = trace {
+                trace(TransformationTraceMessage::UnmatchedRule(self.name))
+            }
+            return Ok(vec![word]);
+        }
+
+        self.all_variants(transformer, word, attributes, trace)
+    }
+
+    // Returns every variant the rule produces when it applies, ignoring exceptions and probability.
+    fn all_variants(&self, transformer: &Transformation, word: Word, attributes: &HashMap<String, String>, trace: Option<&TransformationTraceCallback>) -> Result<Vec<Word>, ElbieError> {
+        let mut result = vec![self.transform(transformer, word.clone(), attributes, trace)?];
+        for alternative in &self.alternatives {
+            let variant = alternative.transform(transformer, word.clone(), attributes, trace)?;
+            if !result.contains(&variant) {
+                result.push(variant);
+            }
+        }
+        if matches!(self.variation, RuleVariation::Optional) && !result.contains(&word) {
+            result.push(word);
+        }
+        Ok(result)
+    }
+}
+
+/// The result of checking an example from `Transformation::add_rule_examples` or `Transformation::add_examples`. The rule is None for an example of the whole transformation. The actual words are an error if the input couldn't be transformed.
+pub(crate) struct ExampleResult {
+    pub rule: Option<&'static str>,
+    pub input: Word,
+    pub expected: Vec<Word>,
+    pub actual: Result<Vec<Word>, ElbieError>
+}
+
+impl ExampleResult {
+    // the variants don't have to be in the same order
+    pub(crate) fn passed(&self) -> bool {
+        self.actual.as_ref().is_ok_and(|actual| self.expected.len() == actual.len() && self.expected.iter().all(|word| actual.contains(word)))
     }
 }
 
 pub struct Transformation {
     inventory: Inventory,
     rules: Vec<Rule>,
-    validation_language: Option<&'static str>
+    validation_language: Option<&'static str>,
+    examples: Vec<(Word, Vec<Word>)>,
+    seed: u64
+}
+
+// The rules are closures, so only their names can be shown.
+impl fmt::Debug for Transformation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transformation")
+         .field("inventory", &self.inventory)
+         .field("rules", &self.rule_names())
+         .field("validation_language", &self.validation_language)
+         .field("examples", &self.examples)
+         .field("seed", &self.seed)
+         .finish()
+    }
 }
 
 impl Transformation {
@@ -393,7 +808,9 @@ impl Transformation {
         let rules = Vec::new();
         let mut result = Self { inventory,
                                 rules,
-                                validation_language: None };
+                                validation_language: None,
+                                examples: Vec::new(),
+                                seed: 0 };
         result.add_language(source);
         result
     }
@@ -440,21 +857,214 @@ impl Transformation {
         self.inventory.extend(inventory, name)
     }
 
+    /// Adds a rule, which is applied to words in the order the rules were added. The rule is applied simultaneously, unless changed with `set_rule_application`.
     pub fn add_rule<Sequence: Fn(&mut RuleState) -> Result<bool, RuleStateError> + 'static>(&mut self, name: &'static str, rule: Sequence) {
         self.rules.push(Rule::new(name, rule));
     }
 
-    /// Applies transformation rules in order, and returns the final word if successful.
-    /// The word has not been validated for any specific language, so this should still be done before reporting the result to the user.
-    pub(crate) fn transform(&self, word: &Word, trace: Option<&TransformationTraceCallback>) -> Result<Word, ElbieError> {
+    pub(crate) fn rule_names(&self) -> Vec<&'static str> {
+        self.rules.iter().map(|rule| rule.name).collect()
+    }
+
+    // If there is more than one rule with the name, the last one added is the one returned.
+    fn rule_mut(&mut self, name: &'static str) -> Result<&mut Rule, ElbieError> {
+        self.rules.iter_mut().rev().find(|rule| rule.name == name).ok_or(ElbieError::UnknownTransformationRule(name))
+    }
+
+    /// Changes how the rule with the specified name is applied to words. The default is `RuleApplication::Simultaneous`. If more than one rule has the name, the last one added is changed.
+    pub fn set_rule_application(&mut self, name: &'static str, application: RuleApplication) -> Result<(), ElbieError> {
+        let rule = self.rule_mut(name)?;
+        rule.application = application;
+        for alternative in &mut rule.alternatives {
+            alternative.application = application;
+        }
+        Ok(())
+    }
+
+    /// Makes the rule with the specified name optional or probabilistic. The default is `RuleVariation::Always`. A probability must be between 0 and 1. If more than one rule has the name, the last one added is changed.
+    pub fn set_rule_variation(&mut self, name: &'static str, variation: RuleVariation) -> Result<(), ElbieError> {
+        if let RuleVariation::Probability(probability) = variation
+           && !(0.0..=1.0).contains(&probability)
+        {
+            return Err(ElbieError::InvalidRuleProbability(name, probability));
+        }
+        self.rule_mut(name)?.variation = variation;
+        Ok(())
+    }
+
+    /// Changes the seed which decides whether probabilistic rules apply to a word. The default is 0. Changing it changes which words a rule applies to, but a word is still transformed the same way every time with the same seed, on any platform.
+    pub const fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    // the decision is made from the seed, the rule and the original word, rather than a shared random number generator, so it doesn't depend on which other words were transformed first. The hash is FNV-1a rather than a std or rand hasher, because those can change between versions, and the same seed should always give the same words.
+    fn random_bool(&self, rule: &'static str, original: &Word, probability: f64) -> bool {
+        // 64-bit FNV-1a, see <http://www.isthe.com/chongo/tech/comp/fnv/>.
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+        // 0xff never appears in UTF-8, so it separates the names without ambiguity.
+        const SEPARATOR: u8 = 0xff;
+
+        let bytes = self.seed.to_le_bytes().into_iter().chain(rule.bytes()).chain(original.phonemes().iter().flat_map(|phoneme| once(SEPARATOR).chain(phoneme.name.bytes())));
+        let hash = bytes.fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME));
+        // the top 53 bits fit exactly in the mantissa, giving a number from 0 up to (but not including) 1.
+        let fraction = (hash >> 11) as f64 / (1_u64 << 53) as f64;
+        fraction < probability
+    }
+
+    /// Adds another way of applying the rule with the specified name. Each alternative is applied to the word separately, and produces another variant of the word, in free variation. The alternatives use the application, exceptions and conditions of the main rule. If more than one rule has the name, the last one added is changed.
+    pub fn add_rule_alternative<Sequence: Fn(&mut RuleState) -> Result<bool, RuleStateError> + 'static>(&mut self, name: &'static str, rule: Sequence) -> Result<(), ElbieError> {
+        let main = self.rule_mut(name)?;
+        let mut alternative = Rule::new(name, rule);
+        alternative.application = main.application;
+        main.alternatives.push(alternative);
+        Ok(())
+    }
+
+    /// Adds words which the rule with the specified name will not change. The words are written in phonemes, with or without slashes, and are compared to the word before any rules were applied. Will return an error if a word can't be read with the transformation's inventory. If more than one rule has the name, the last one added is changed.
+    pub fn add_rule_exceptions(&mut self, name: &'static str, words: &[&str]) -> Result<(), ElbieError> {
+        let words = words.iter().map(|word| self.read_word(word)).collect::<Result<Vec<_>, _>>()?;
+        self.rule_mut(name)?.exceptions.extend(words);
+        Ok(())
+    }
+
+    /// Only applies the rule with the specified name to words which have the attribute with the specified value, such as "part-of-speech" = "noun". When transforming from the command line, the attributes come from the columns of the CSV file. If a rule has more than one condition, all of them must be met.
+    pub fn add_rule_condition(&mut self, name: &'static str, attribute: &str, value: &str) -> Result<(), ElbieError> {
+        self.rule_mut(name)?.conditions.push((attribute.to_owned(), value.to_owned(), true));
+        Ok(())
+    }
+
+    /// Doesn't apply the rule with the specified name to words which have the attribute with the specified value, such as "stratum" = "loan". See `add_rule_condition`.
+    pub fn add_rule_exclusion(&mut self, name: &'static str, attribute: &str, value: &str) -> Result<(), ElbieError> {
+        self.rule_mut(name)?.conditions.push((attribute.to_owned(), value.to_owned(), false));
+        Ok(())
+    }
+
+    /// Adds examples of what the rule with the specified name does, as pairs of input and expected output written in phonemes, with or without slashes. If the rule produces more than one variant, separate them in the output with '~'. The examples are checked by the `check-transformations` command, which applies the rule by itself to each input, ignoring exceptions, conditions and probability. Will return an error if an example can't be read with the transformation's inventory. If more than one rule has the name, the last one added is changed.
+    pub fn add_rule_examples(&mut self, name: &'static str, examples: &[(&str, &str)]) -> Result<(), ElbieError> {
+        let examples = self.read_examples(examples)?;
+        self.rule_mut(name)?.examples.extend(examples);
+        Ok(())
+    }
+
+    /// Adds examples of what the whole transformation does, written like those for `add_rule_examples`. These are checked by applying every rule in order, so they catch problems with the order of the rules. Will return an error if an example can't be read with the transformation's inventory.
+    pub fn add_examples(&mut self, examples: &[(&str, &str)]) -> Result<(), ElbieError> {
+        let examples = self.read_examples(examples)?;
+        self.examples.extend(examples);
+        Ok(())
+    }
+
+    fn read_examples(&self, examples: &[(&str, &str)]) -> Result<Vec<(Word, Vec<Word>)>, ElbieError> {
+        examples.iter()
+                .map(|(input, output)| {
+                    let input = self.read_word(input)?;
+                    let output = output.split('~').map(|variant| self.read_word(variant)).collect::<Result<_, _>>()?;
+                    Ok((input, output))
+                })
+                .collect()
+    }
+
+    /// Runs the examples for every rule, and then for the whole transformation, and returns the results. An example which can't be transformed has an error as its result, so the rest can still be checked.
+    pub(crate) fn check_examples(&self) -> Vec<ExampleResult> {
+        let attributes = HashMap::new();
+        let mut result = Vec::new();
+        for rule in &self.rules {
+            for (input, expected) in &rule.examples {
+                result.push(ExampleResult { rule: Some(rule.name),
+                                            input: input.clone(),
+                                            expected: expected.clone(),
+                                            actual: rule.all_variants(self, input.clone(), &attributes, None) });
+            }
+        }
+        for (input, expected) in &self.examples {
+            result.push(ExampleResult { rule: None,
+                                        input: input.clone(),
+                                        expected: expected.clone(),
+                                        actual: self.transform(input, &attributes, None) });
+        }
+        result
+    }
+
+    /// Reads a word written in phonemes from the transformation's inventory, with or without slashes, like the examples.
+    pub(crate) fn read_word(&self, input: &str) -> Result<Word, ElbieError> {
+        self.inventory.read_word(input.trim().trim_matches('/'))
+    }
+
+    /// Searches for the words in the source language which transform into each of the specified words, by transforming every valid word up to `max_length` phonemes. The result has a list of sources for each word. The number of valid words grows very quickly with the length, and every one is transformed, so keep it small.
+    pub(crate) fn find_sources(&self, source: &Language, words: &[Word], max_length: usize) -> Result<Vec<Vec<Word>>, ElbieError> {
+        let attributes = HashMap::new();
+        let mut result = vec![Vec::new(); words.len()];
+        for candidate in source.enumerate_words(max_length)? {
+            for variant in self.transform(&candidate, &attributes, None)? {
+                for (word, sources) in words.iter().zip(&mut result) {
+                    if *word == variant && !sources.contains(&candidate) {
+                        sources.push(candidate.clone());
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Parses a rule written in sound change notation, such as `s > z / vowel_vowel` or `{p,t,k} > {b,d,g} / [+nasal]_`, and adds it as a rule like `add_rule` does. Phoneme and set names must be in the transformation's inventory, so add any other inventories first. Names are matched longest first, so if a set name contains an underscore, such as `open_mid`, put spaces around the `_` that marks the target (`open_mid _`). See the notes in `sound_change.rs` for the full notation.
+    pub fn add_sound_change(&mut self, name: &'static str, notation: &str) -> Result<(), ElbieError> {
+        let sound_change = SoundChange::parse(notation, &self.inventory)?;
+        self.add_rule(name, move |rule| sound_change.apply(rule));
+        Ok(())
+    }
+
+    /// Applies transformation rules in order, and returns the final words if successful. There will be more than one word if any of the rules are optional or have alternatives (see `set_rule_variation`), otherwise there is just one.
+    /// The words have not been validated for any specific language, so this should still be done before reporting the result to the user.
+    /// The attributes are made available to the rules, see `RuleState::attribute` and `add_rule_condition`.
+    pub(crate) fn transform(&self, word: &Word, attributes: &HashMap<String, String>, trace: Option<&TransformationTraceCallback>) -> Result<Vec<Word>, ElbieError> {
         if let Some(trace) = trace {
             trace(TransformationTraceMessage::StartTransformation(word.clone()))
         }
-        let mut transformed = word.clone();
+        let mut variants = vec![word.clone()];
         for rule in &self.rules {
-            transformed = rule.transform(self, transformed, trace)?
+            let mut next = Vec::new();
+            for variant in variants {
+                for transformed in rule.transform_variants(self, variant, word, attributes, trace)? {
+                    if !next.contains(&transformed) {
+                        next.push(transformed);
+                    }
+                }
+            }
+            variants = next;
         }
-        Ok(transformed)
+        Ok(variants)
+    }
+
+    /// Applies transformation rules in order like `transform`, but returns the name of every rule along with the variants it produced, if the rule changed any of them. Each of the words is transformed separately, as the variants from a previous transformation in a chain would be, and the variants from all of them are combined.
+    pub(crate) fn derive(&self, words: &[Word], attributes: &HashMap<String, String>) -> Result<Derivation, ElbieError> {
+        let mut result = Vec::new();
+        // each variant is paired with the word it came from, because exceptions are compared to that.
+        let mut variants: Vec<(&Word, Word)> = words.iter().map(|word| (word, word.clone())).collect();
+        let mut forms = words.to_vec();
+        for rule in &self.rules {
+            let mut next = Vec::new();
+            for (original, variant) in &variants {
+                for transformed in rule.transform_variants(self, variant.clone(), original, attributes, None)? {
+                    if !next.contains(&(*original, transformed.clone())) {
+                        next.push((*original, transformed));
+                    }
+                }
+            }
+            let mut changed = Vec::new();
+            for (_, variant) in &next {
+                if !changed.contains(variant) {
+                    changed.push(variant.clone());
+                }
+            }
+            if changed == forms {
+                result.push((rule.name, None));
+            } else {
+                result.push((rule.name, Some(changed.clone())));
+                forms = changed;
+            }
+            variants = next;
+        }
+        Ok(result)
     }
 }
 
@@ -497,22 +1107,75 @@ impl<'vec> IntoIterator for &'vec TransformationSet {
     }
 }
 
+/// A list of transformations which are applied one after another, each to the result of the one before. Each step is a source language and transformation name, so the chain can pass through several languages.
+pub struct TransformationChain {
+    steps: Vec<(String, String)>
+}
+
+impl TransformationChain {
+    pub(crate) const fn new(steps: Vec<(String, String)>) -> Self {
+        Self { steps }
+    }
+
+    pub(crate) fn steps(&self) -> &[(String, String)] {
+        &self.steps
+    }
+}
+
+/// A list of dated stages, each of which is a source language and the name of a transformation from that language, sorted by date. See `Family::timeline`.
+pub struct Timeline {
+    stages: Vec<(i64, String, String)>
+}
+
+impl Timeline {
+    pub(crate) fn new(stages: &[(i64, &'static str, &'static str)]) -> Self {
+        let mut stages: Vec<_> = stages.iter().map(|(date, from, name)| (*date, (*from).to_owned(), (*name).to_owned())).collect();
+        stages.sort_by_key(|(date, _, _)| *date);
+        Self { stages }
+    }
+
+    pub(crate) fn stages(&self) -> &[(i64, String, String)] {
+        &self.stages
+    }
+}
+
+// A stage of a timeline, ready to transform words.
+pub(crate) struct TimelineStage<'family> {
+    pub date: i64,
+    pub language: &'family Language,
+    pub transformations: Vec<PreparedTransformation<'family, 'family>>
+}
+
 pub enum TransformationEntry {
     Single(Transformation),
-    Set(TransformationSet)
+    Set(TransformationSet),
+    Chain(TransformationChain)
+}
+
+/// What a word became in one of the transformations of a set or chain. See `Family::transform_word_with_set`.
+pub struct TransformationResult {
+    /// The name of the transformation.
+    pub name: String,
+    /// Every variant of the result. The first is the one where every optional rule applied.
+    pub words: Vec<Word>,
+    /// None if the transformation doesn't validate, or validation wasn't asked for.
+    pub valid: Option<bool>
 }
 
-// this is used in a list when loading transformations from a set
+// this is used in a list when loading transformations from a set or chain
 pub(crate) struct PreparedTransformation<'transformation, 'language> {
     pub name: String,
     pub transformation: &'transformation Transformation,
-    pub validator: Option<&'language Language>
+    pub validator: Option<&'language Language>,
+    // if true, this is applied to the result of the previous transformation in the list, instead of the original word.
+    pub chained: bool
 }
 
 impl<'transformation, 'language> PreparedTransformation<'transformation, 'language> {
     pub(crate) const fn new(name: String, transformation: &'transformation Transformation, validator: Option<&'language Language>) -> Self {
         Self { name,
                transformation,
-               validator }
+               validator,
+               chained: false }
     }
 }
@@ -4,7 +4,7 @@ use core::fmt::Display;
 use core::fmt::Formatter;
 use std::rc::Rc;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Word {
     phonemes: Vec<Rc<Phoneme>>
 }
@@ -37,6 +37,12 @@ impl Word {
     pub const fn to_raw_display(&'_ self) -> RawWord<'_> {
         RawWord(self)
     }
+
+    /// Displays the word in square brackets, for narrow phonetic transcriptions.
+    #[must_use]
+    pub const fn to_phonetic_display(&'_ self) -> PhoneticWord<'_> {
+        PhoneticWord(self)
+    }
 }
 
 impl Display for Word {
@@ -59,6 +65,14 @@ impl Display for RawWord<'_> {
     }
 }
 
+pub struct PhoneticWord<'word>(&'word Word);
+
+impl Display for PhoneticWord<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", RawWord(self.0))
+    }
+}
+
 impl From<Vec<Rc<Phoneme>>> for Word {
     fn from(phonemes: Vec<Rc<Phoneme>>) -> Self {
         Self { phonemes }
@@ -5,6 +5,7 @@ use crate::grid::Grid;
 use crate::grid::GridRow;
 use crate::grid::TRBodyClass;
 use crate::grid::TableClass;
+use crate::script::Script;
 use core::error::Error;
 use core::iter;
 use core::mem;
@@ -44,11 +45,17 @@ impl WordTableEntry {
     pub(crate) const fn word(&self) -> &String {
         &self.word
     }
+
+    pub(crate) const fn attributes(&self) -> &HashMap<String, String> {
+        &self.attributes
+    }
 }
 
 #[derive(Default)]
 pub(crate) struct WordTable {
     attribute_names: Vec<String>,
+    // the scripts for attributes which are spelled in an orthography that has one
+    attribute_scripts: HashMap<String, Script>,
     entries: Vec<WordTableEntry>
 }
 
@@ -94,6 +101,7 @@ impl WordTable {
         }
 
         Ok(Self { attribute_names,
+                  attribute_scripts: HashMap::new(),
                   entries })
     }
 
@@ -121,6 +129,14 @@ impl WordTable {
         }
     }
 
+    /// Adds an attribute which is spelled in an orthography, so its cells are displayed in the orthography's script, if it has one.
+    pub(crate) fn add_spelling_attribute(&mut self, name: String, script: Option<&Script>) {
+        if let Some(script) = script {
+            _ = self.attribute_scripts.insert(name.clone(), script.clone());
+        }
+        self.add_attribute(name);
+    }
+
     pub(crate) fn remove_attribute(&mut self, name: &str) {
         self.attribute_names.retain(|n| n != name);
     }
@@ -133,6 +149,7 @@ impl WordTable {
         for attr_name in words.attribute_names {
             self.add_attribute(attr_name);
         }
+        self.attribute_scripts.extend(words.attribute_scripts);
         for entry in words.entries {
             self.entries.push(entry);
         }
@@ -145,7 +162,12 @@ impl WordTable {
             let mut row = GridRow::new(TRBodyClass::BodyRow);
             row.push_cell(Cell::content(entry.word, None));
             for attr_name in &self.attribute_names {
-                row.push_cell(Cell::content(entry.attributes.get(attr_name).cloned().unwrap_or_else(String::new), None));
+                let cell = Cell::content(entry.attributes.get(attr_name).cloned().unwrap_or_else(String::new), None);
+                if let Some(script) = self.attribute_scripts.get(attr_name) {
+                    row.push_cell(script.style_cell(&mut grid, cell));
+                } else {
+                    row.push_cell(cell);
+                }
             }
             grid.push_body_row(row);
         }
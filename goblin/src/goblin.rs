@@ -527,12 +527,8 @@ pub(crate) mod to_hobgoblin {
                           Ok(true)
                       });
 
-        transformation.add_rule("voiceless r loss", |rule| {
-                          // at this point the voiceless r is completely lost
-                          rule.repl(VOICELESS_R, &[TURNED_R])?;
-
-                          Ok(true)
-                      });
+        // at this point the voiceless r is completely lost
+        transformation.add_sound_change("voiceless r loss", &format!("{VOICELESS_R} > {TURNED_R}"))?;
 
         transformation.add_rule("syllabbification hiatus", |rule| {
                           // If two syllabified consonants are paired, they gain an hiatus equal to the unsyallabified version of the firstsyllable.
@@ -594,6 +590,17 @@ pub(crate) mod to_hobgoblin {
                           Ok(true)
                       });
 
+        // run these with `check-transformations`, especially after moving rules around.
+        transformation.add_rule_examples("aspirated to affricate", &[("ɣʰlid", "k͜xlid"), ("vʰan", "p͜ɸan")])?;
+        transformation.add_rule_examples("palatalize and break affricates", &[("k͜xiŋ", "kʃiŋ")])?;
+        transformation.add_rule_examples("break non-final affricates", &[("k͜xlid", "kxlid")])?;
+        transformation.add_rule_examples("consonant softening", &[("tɒlaɡɒpi", "tɒlaɣɒpi")])?;
+        transformation.add_rule_examples("syllabification (1)", &[("pan", "pn̩")])?;
+        transformation.add_rule_examples("voiceless r loss", &[("kxɹ̥u", "kxɹu")])?;
+        transformation.add_rule_examples("reduction of clusters and affricates", &[("kxlid", "klid")])?;
+        transformation.add_rule_examples("merge some diphthongs and vowel clusters", &[("ɲɒi̯", "ɲi")])?;
+        transformation.add_examples(&[("ɣʰlid", "klid"), ("ɲɒi̯", "ɲi"), ("du", "du")])?;
+
         Ok(transformation)
     }
 